@@ -1,8 +1,11 @@
 use common::raw_input::parse_u32_unchecked;
 use common::{input_size, read_input};
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> i32 {
+#[cfg(test)]
+const EXAMPLE: &str = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n";
+
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 3))]
+fn part1() -> i32 {
     let mut input_pos = 0;
     let mut result = 0;
     let mut position: i32 = 50;
@@ -1,9 +1,7 @@
-use common::raw_input::{parse_u64_unchecked};
-use common::{input_size, set_input};
-use common::raw_array::RawFastArray;
+use common::raw_input::InputCursor;
 use common::raw_i64::{iter_non_empty_range_inclusive, RawI64};
 
-const POWERS_OF_TEN: [i64; 15] = [
+common::lookup_table!(POWERS_OF_TEN, 0, [
     1,
     10,
     100,
@@ -19,29 +17,24 @@ const POWERS_OF_TEN: [i64; 15] = [
     1_000_000_000_000,
     10_000_000_000_000,
     100_000_000_000_000,
-];
+]);
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
-    let mut input_pos = 0;
-    let mut result = RawI64::new(0);
-    let input_size = input_size();
-    // replace final line break with comma so we don't need to special case that
-    set_input(input_size - 1, ',' as i64);
+#[cfg(test)]
+const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124\n";
 
-    let mut lookup_table = RawFastArray::new_leaked(POWERS_OF_TEN.len());
-    for (i, power) in POWERS_OF_TEN.iter().enumerate() {
-        lookup_table.set(i, *power);
-    }
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 1227775554))]
+fn part1() -> RawI64 {
+    let mut input = InputCursor::new();
+    let mut result = RawI64::new(0);
 
-    loop {
-        if input_pos >= input_size {
-            break;
-        }
-        let from: RawI64 = (unsafe { parse_u64_unchecked(&mut input_pos, '-') } as i64).into();
-        let to: RawI64 = (unsafe { parse_u64_unchecked(&mut input_pos, ',') } as i64).into();
+    while !input.at_end() {
+        let from: RawI64 = (input.parse_u64_unchecked() as i64).into();
+        input.expect_unchecked('-');
+        let to: RawI64 = (input.parse_u64_unchecked() as i64).into();
+        // ',' or the final line break
+        input.skip(1);
         for value in iter_non_empty_range_inclusive(from, to) {
-            if is_invalid(value, &lookup_table) {
+            if is_invalid(value) {
                 result += value
             }
         }
@@ -51,15 +44,15 @@ pub extern "C" fn solve() -> RawI64 {
 }
 
 
-fn is_invalid(value: RawI64, powers_of_ten: &RawFastArray) -> bool {
+fn is_invalid(value: RawI64) -> bool {
     let len = value.digit_len();
-    if len % 2 != 0 {
+    if !len.is_multiple_of(2) {
         return false
     }
 
-    let power_of_ten = powers_of_ten.get((len / 2) as usize);
+    let power_of_ten = POWERS_OF_TEN.get((len / 2) as usize);
     let bottom_half = (value % power_of_ten.into()).to_i64() as u32;
     let top_half = (value / power_of_ten.into()).to_i64() as u32;
 
     top_half == bottom_half
-}
\ No newline at end of file
+}
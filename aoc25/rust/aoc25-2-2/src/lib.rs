@@ -1,9 +1,8 @@
 use common::raw_input::{parse_u64_unchecked};
 use common::{input_size, set_input};
-use common::raw_array::RawFastArray;
 use common::raw_i64::{iter_non_empty_range_inclusive, RawI64};
 
-const POWERS_OF_TEN: [i64; 15] = [
+common::lookup_table!(POWERS_OF_TEN, 0, [
     1,
     10,
     100,
@@ -19,21 +18,19 @@ const POWERS_OF_TEN: [i64; 15] = [
     1_000_000_000_000,
     10_000_000_000_000,
     100_000_000_000_000,
-];
+]);
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
+#[cfg(test)]
+const EXAMPLE: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124\n";
+
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 4174379265))]
+fn part2() -> RawI64 {
     let mut input_pos = 0;
     let mut result = RawI64::new(0);
     let input_size = input_size();
     // replace final line break with comma so we don't need to special case that
     set_input(input_size - 1, ',' as i64);
 
-    let mut lookup_table = RawFastArray::new_leaked(POWERS_OF_TEN.len());
-    for (i, power) in POWERS_OF_TEN.iter().enumerate() {
-        lookup_table.set(i, *power);
-    }
-
     loop {
         if input_pos >= input_size {
             break;
@@ -41,7 +38,7 @@ pub extern "C" fn solve() -> RawI64 {
         let from: RawI64 = (unsafe { parse_u64_unchecked(&mut input_pos, '-') } as i64).into();
         let to: RawI64 = (unsafe { parse_u64_unchecked(&mut input_pos, ',') } as i64).into();
         for value in iter_non_empty_range_inclusive(from, to) {
-            if is_invalid(value, &lookup_table) {
+            if is_invalid(value) {
                 result += value
             }
         }
@@ -50,12 +47,12 @@ pub extern "C" fn solve() -> RawI64 {
     result
 }
 
-fn is_invalid(value: RawI64, powers_of_ten: &RawFastArray) -> bool {
+fn is_invalid(value: RawI64) -> bool {
     let len = value.digit_len();
     'outer: for part_len in 1..=(len / 2) {
-        if len % part_len != 0 { continue }
+        if !len.is_multiple_of(part_len) { continue }
         let part_count = len / part_len;
-        let power_of_ten = powers_of_ten.get(part_len as usize);
+        let power_of_ten = POWERS_OF_TEN.get(part_len as usize);
 
         let prev_part = value % power_of_ten.into();
         let mut remaining = value / power_of_ten.into();
@@ -64,9 +61,9 @@ fn is_invalid(value: RawI64, powers_of_ten: &RawFastArray) -> bool {
             if current_part != prev_part {
                 continue 'outer
             }
-            remaining = remaining / power_of_ten.into();
+            remaining /= power_of_ten.into();
         }
         return true
     }
     false
-}
\ No newline at end of file
+}
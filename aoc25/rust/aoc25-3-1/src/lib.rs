@@ -3,8 +3,11 @@ use common::raw_input::find_next_unsafe;
 
 const DIGITS: u32 = 2;
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> u32 {
+#[cfg(test)]
+const EXAMPLE: &str = "987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
+
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 357))]
+fn part1() -> u32 {
     let mut input_pos = 0;
     let mut result: u32 = 0;
     let input_size = input_size();
@@ -23,9 +26,11 @@ pub extern "C" fn solve() -> u32 {
 
             'num: for num in (1..=9).rev() {
                 let char = num + '0' as u32;
+                // input_pos is only changed right before breaking out of the loop
+                #[allow(clippy::mut_range_bound)]
                 for pos in input_pos..last_char_pos {
                     let input_char = read_input_raw(pos);
-                    if input_char.subabs((char as i64).into()) == 0.into() {
+                    if input_char.subabs((char as i64).into()).is_zero() {
                         input_pos = pos + 1;
                         row_result += num;
                         break 'num;
@@ -40,4 +45,3 @@ pub extern "C" fn solve() -> u32 {
 
     result
 }
-
@@ -4,8 +4,11 @@ use common::{input_size, read_input_raw};
 
 const DIGITS: u32 = 12;
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
+#[cfg(test)]
+const EXAMPLE: &str = "987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
+
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 3121910778619))]
+fn part2() -> RawI64 {
     let mut input_pos = 0;
     let mut result: RawI64 = RawI64::new(0);
     let input_size = input_size();
@@ -24,9 +27,11 @@ pub extern "C" fn solve() -> RawI64 {
 
             'num: for num in (1..=9).rev() {
                 let char = num + '0' as u32;
+                // input_pos is only changed right before breaking out of the loop
+                #[allow(clippy::mut_range_bound)]
                 for pos in input_pos..last_char_pos {
                     let input_char = read_input_raw(pos);
-                    if input_char.subabs((char as i64).into()) == 0.into() {
+                    if input_char.subabs((char as i64).into()).is_zero() {
                         input_pos = pos + 1;
                         row_result += (num as i64).into();
                         break 'num;
@@ -13,8 +13,11 @@ const AROUND: [(i32, i32); 8] = [
     (1, 1),
 ];
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> u32 {
+#[cfg(test)]
+const EXAMPLE: &str = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n.@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.\n";
+
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 43))]
+fn part2() -> u32 {
     let mut result: u32 = 0;
     let input_size = input_size();
 
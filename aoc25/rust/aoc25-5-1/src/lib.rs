@@ -1,8 +1,11 @@
 use common::{input_size, read_input_raw};
 use common::raw_input::{is_char, parse_u64_unchecked};
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> u32 {
+#[cfg(test)]
+const EXAMPLE: &str = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n";
+
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 3))]
+fn part1() -> u32 {
     let mut input_pos: u32 = 0;
     let mut result: u32 = 0;
     let input_size = input_size();
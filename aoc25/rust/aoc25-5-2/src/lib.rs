@@ -1,34 +1,38 @@
 use common::{input_size, read_input_raw};
+use common::instructions::{add_unchecked, and, div_unchecked, is_nonzero, mul_unchecked};
 use common::raw_input::{is_char, parse_u64_unchecked};
+use common::raw_vec::RawFastVec;
+use common::sort::sort;
 
-#[derive(Clone)]
-struct RangePart {
-    id: i64,
-    is_end: bool
-}
+#[cfg(test)]
+const EXAMPLE: &str = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n";
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> i64 {
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 14))]
+fn part2() -> i64 {
     let mut input_pos: u32 = 0;
     let mut result: i64 = 0;
     let input_size = input_size();
 
     let range_count = count_ranges(input_size);
-    let mut ranges: Vec<RangePart> = Vec::with_capacity((range_count * 2) as usize);
+    // Range parts are encoded as `id * 2 + is_end` so they fit into a single raw cell.
+    // This also sorts starts before ends if there is a tie, we would double-count the id if that is where two ranges meet.
+    // Ids are non-negative and far from overflowing, so the unchecked instructions are enough for the encoding.
+    let mut ranges = RawFastVec::with_capacity((range_count * 2) as usize);
     for _ in 0..range_count {
         let from = unsafe { parse_u64_unchecked(&mut input_pos, '-') } as i64;
-        ranges.push(RangePart{ id: from, is_end: false });
+        ranges.push(mul_unchecked(from, 2));
         let to = unsafe { parse_u64_unchecked(&mut input_pos, '\n') } as i64;
-        ranges.push(RangePart{ id: to, is_end: true });
+        ranges.push(add_unchecked(mul_unchecked(to, 2), 1));
     }
 
-    // we need to sort starts before ends if there is a tie or we would double-count the id if that is where two ranges meet.
-    ranges.sort_by_key(|x| (x.id, x.is_end));
+    sort(ranges.as_mut_slice());
 
     let mut depth: u32 = 0;
     let mut active_from: Option<i64> = None;
-    for part in ranges {
-        if part.is_end {
+    for part in &ranges {
+        let id = div_unchecked(part, 2);
+        let is_end = is_nonzero(and(part, 1));
+        if is_end {
             depth -= 1;
         } else {
             depth += 1;
@@ -37,12 +41,12 @@ pub extern "C" fn solve() -> i64 {
         if let Some(from) = active_from {
             if depth == 0 {
                 // No more ranges active
-                let to = part.id;
+                let to = id;
                 result += to - from + 1;
                 active_from = None;
             }
         } else {
-            active_from = Some(part.id);
+            active_from = Some(id);
         }
     }
 
@@ -3,8 +3,11 @@ use common::{input_size, read_input_raw, set_input};
 use common::input_utils::{count_chars, count_other_chars, find_first_other_char};
 use common::raw_i64::RawI64;
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
+#[cfg(test)]
+const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 4277556))]
+fn part1() -> RawI64 {
     let mut result: RawI64 = 0.into();
     let input_size = input_size();
 
@@ -43,4 +46,4 @@ pub extern "C" fn solve() -> RawI64 {
     }
 
     result
-}
\ No newline at end of file
+}
@@ -3,8 +3,11 @@ use common::{input_size, read_input, read_input_raw, set_input};
 use common::input_utils::{count_chars, count_other_chars, find_first_other_char};
 use common::raw_i64::RawI64;
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
+#[cfg(test)]
+const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 3263827))]
+fn part2() -> RawI64 {
     let mut result: RawI64 = 0.into();
     let input_size = input_size();
 
@@ -48,10 +51,9 @@ fn parse_u32_vertical(from_y: u32, to_y: u32, x: u32, row_len: u32) -> u32 {
     for y in from_y..to_y {
         let input_pos = y * row_len + x;
         let c = unsafe { std::char::from_u32_unchecked(read_input(input_pos) as u32) };
-        if c >= '0' && c <= '9' {
+        if c.is_ascii_digit() {
             result = result * 10 + (c as u32 - '0' as u32);
         }
     }
     result
 }
-
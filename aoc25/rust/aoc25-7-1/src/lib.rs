@@ -3,8 +3,11 @@ use common::raw_i64::RawI64;
 use common::raw_input::{is_char};
 use common::{input_size, read_input_raw, set_input};
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
+#[cfg(test)]
+const EXAMPLE: &str = ".......S.......\n...............\n.......^.......\n...............\n......^.^......\n...............\n.....^.^.^.....\n...............\n....^.^...^....\n...............\n...^.^...^.^...\n...............\n..^...^.....^..\n...............\n.^.^.^.^.^...^.\n...............\n";
+
+#[common::ksplang_solve(part = 1, example = (EXAMPLE, 21))]
+fn part1() -> RawI64 {
     let mut result: RawI64 = 0.into();
     let input_size = input_size();
 
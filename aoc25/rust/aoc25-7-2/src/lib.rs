@@ -1,52 +1,44 @@
-use common::input_utils::{count_chars, find_first_char};
+use common::failure::{OrFail, MISSING_INPUT};
+use common::grid::InputGrid;
 use common::raw_i64::RawI64;
-use common::raw_input::{is_char};
-use common::{input_size, read_input_raw, set_input};
 
-#[unsafe(no_mangle)]
-pub extern "C" fn solve() -> RawI64 {
-    let input_size = input_size();
+#[cfg(test)]
+const EXAMPLE: &str = ".......S.......\n...............\n.......^.......\n...............\n......^.^......\n...............\n.....^.^.^.....\n...............\n....^.^...^....\n...............\n...^.^...^.^...\n...............\n..^...^.....^..\n...............\n.^.^.^.^.^...^.\n...............\n";
 
-    let rows = count_chars(0, input_size, '\n');
+#[common::ksplang_solve(part = 2, example = (EXAMPLE, 40))]
+fn part2() -> RawI64 {
+    let grid = InputGrid::new();
+    let rows = grid.height();
+    let width = grid.width();
 
-    let row_len = input_size / rows;
-
-    let start = find_first_char(0, input_size, 'S');
-    set_input(start, 1); // 1 beam
+    let (start_x, start_y) = grid.find('S').or_fail(MISSING_INPUT);
 
     // replace all dots with zeroes - we will use it to track beams on each location
-    for i in 0..input_size {
-        let char = read_input_raw(i);
-        if is_char(char, '.') {
-            set_input(i, 0);
-        } else if is_char(char, '^') {
-            set_input(i, -1); // splits are -1
-        }
-    }
+    // splits are -1, S is 1 beam
+    grid.rewrite(&[('.', 0), ('^', -1)]);
+    grid.set(start_x, start_y, 1);
 
     // we do not care about bottom row
     for row in 1..rows - 1 {
         // could optimize here: increase x range by 1 to each side with each row, start with just one column under S
-        for x in 0..(row_len - 1) {
-            // -1 for newline
-            let char = read_input_xy(x, row, row_len);
-            let num_above = read_input_xy(x, row - 1, row_len);
+        for x in 0..width {
+            let char = grid.get(x, row);
+            let num_above = grid.get(x, row - 1);
 
-            if char.subabs((-1).into()) == 0.into() { // char == -1
+            if char.subabs((-1).into()).is_zero() { // char == -1
                 // this is a splitter
 
                 // add number from above to tiles left and right
-                let left = read_input_xy(x - 1, row, row_len);
-                set_input_xy(x - 1, row, row_len, left + num_above);
-                let right = read_input_xy(x + 1, row, row_len);
-                set_input_xy(x + 1, row, row_len, right + num_above);
+                let left = grid.get(x - 1, row);
+                grid.set(x - 1, row, (left + num_above).into());
+                let right = grid.get(x + 1, row);
+                grid.set(x + 1, row, (right + num_above).into());
             } else {
                 // not a splitter
                 // copy from above
-                if num_above.subabs((-1).into()) != 0.into() { // char == -1
+                if num_above.subabs((-1).into()).is_nonzero() { // num_above != -1
                     // number above is not a splitter, we can copy
-                    let num = read_input_xy(x, row, row_len);
-                    set_input_xy(x, row, row_len, num + num_above);
+                    grid.set(x, row, (char + num_above).into());
                 }
             }
         }
@@ -54,9 +46,8 @@ pub extern "C" fn solve() -> RawI64 {
 
     // sum up bottom row
     let mut total_beams = RawI64::from(0);
-    for x in 0..(row_len - 1) {
-        let num = read_input_xy(x, rows - 2, row_len);
-        if num.subabs((-1).into()) == 0.into() {
+    for num in grid.row(rows - 2) {
+        if num.subabs((-1).into()).is_zero() {
             // ignore this splitter
             continue;
         }
@@ -64,13 +55,3 @@ pub extern "C" fn solve() -> RawI64 {
     }
     total_beams
 }
-
-fn read_input_xy(x: u32, y: u32, row_len: u32) -> RawI64 {
-    let index = y * row_len + x;
-    read_input_raw(index)
-}
-
-fn set_input_xy(x: u32, y: u32, row_len: u32, num: RawI64) {
-    let index = y * row_len + x;
-    set_input(index, num.into())
-}
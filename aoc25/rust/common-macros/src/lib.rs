@@ -0,0 +1,120 @@
+//! Procedural macros for the solution crates, re-exported from `common`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Error, Expr, ExprTuple, ItemFn, LitInt};
+
+/// Turns a function into the entry point of a solution.
+///
+/// ```ignore
+/// #[common::ksplang_solve(part = 1, example = (EXAMPLE, 3))]
+/// fn part1() -> i32 { ... }
+/// ```
+///
+/// Generates:
+/// - `solve()`, the WASM export called by the host. The value returned by the function is converted
+///   with `common::output::IntoAnswer`, so `solve()` returns either `common::raw_i64::RawI64` for numbers
+///   or `common::output::Output`.
+/// - `PART`, the part number of the puzzle (1 or 2).
+/// - `run(input: &str)` (not in WASM), which runs the solution on the given text input natively.
+///   It returns `i64` for numbers and `Vec<i64>` for an `Output`.
+/// - a `#[test]` for every `example = (input, answer)` pair, there can be any number of them.
+#[proc_macro_attribute]
+pub fn ksplang_solve(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut part: Option<LitInt> = None;
+    let mut examples: Vec<(Expr, Expr)> = Vec::new();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("part") {
+            let value: LitInt = meta.value()?.parse()?;
+            let number: u32 = value.base10_parse()?;
+            if number != 1 && number != 2 {
+                return Err(Error::new(value.span(), "part has to be 1 or 2"));
+            }
+            part = Some(value);
+            Ok(())
+        } else if meta.path.is_ident("example") {
+            let tuple: ExprTuple = meta.value()?.parse()?;
+            if tuple.elems.len() != 2 {
+                return Err(Error::new_spanned(tuple, "expected an (input, answer) pair"));
+            }
+            let mut elems = tuple.elems.into_iter();
+            examples.push((elems.next().unwrap(), elems.next().unwrap()));
+            Ok(())
+        } else {
+            Err(meta.error("unsupported ksplang_solve attribute, expected `part` or `example`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
+    let function = parse_macro_input!(item as ItemFn);
+    match expand(part, examples, function) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(part: Option<LitInt>, examples: Vec<(Expr, Expr)>, function: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let Some(part) = part else {
+        return Err(Error::new(Span::call_site(), "missing `part = 1` or `part = 2`"));
+    };
+    let signature = &function.sig;
+    if !signature.inputs.is_empty() {
+        return Err(Error::new_spanned(&signature.inputs, "the solution function cannot have parameters"));
+    }
+    if signature.asyncness.is_some() || !signature.generics.params.is_empty() {
+        return Err(Error::new_spanned(signature, "the solution function has to be a plain function"));
+    }
+    let syn::ReturnType::Type(_, return_type) = &signature.output else {
+        return Err(Error::new_spanned(signature, "the solution function has to return the answer"));
+    };
+    let answer_type = quote! { <#return_type as ::common::output::IntoAnswer>::Answer };
+    let value_type = quote! { <#answer_type as ::common::output::Answer>::Value };
+    let name = &signature.ident;
+    if name == "solve" || name == "run" {
+        return Err(Error::new_spanned(name, "this name is used by the generated code, use e.g. `part1` instead"));
+    }
+
+    let tests = examples.iter().enumerate().map(|(i, (input, answer))| {
+        let test_name = format_ident!("example_{}", i + 1);
+        quote! {
+            #[test]
+            fn #test_name() {
+                let expected: #value_type = #answer;
+                assert_eq!(run(#input), expected);
+            }
+        }
+    });
+    let tests = if examples.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #[cfg(test)]
+            mod ksplang_solve_examples {
+                use super::*;
+
+                #(#tests)*
+            }
+        }
+    };
+
+    Ok(quote! {
+        #function
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn solve() -> #answer_type {
+            ::common::output::IntoAnswer::into_answer(#name())
+        }
+
+        /// The part of the puzzle this crate solves.
+        pub const PART: u32 = #part;
+
+        /// Runs the solution natively with the given text input.
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn run(input: &str) -> #value_type {
+            ::common::output::Answer::value(&::common::native::run_with_input(input, solve))
+        }
+
+        #tests
+    })
+}
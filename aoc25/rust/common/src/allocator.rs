@@ -0,0 +1,282 @@
+//! A global allocator tuned for the ksplang translation.
+//!
+//! The default allocator keeps its metadata in WASM memory, where every i32 is 4 separate byte cells
+//! on the ksplang stack, so every allocation costs many byte loads and stores. [KsplangAllocator]
+//! keeps all of its bookkeeping (bump pointer, free lists) in raw i64 cells instead:
+//!
+//! - Small blocks (up to [MAX_SMALL_SIZE] bytes) are rounded up to a power of two size class.
+//!   Freed blocks go to a free list of their class, the link is a raw i64 cell inside the freed block.
+//! - Everything else is bumped from fresh memory. Freed large blocks are only reclaimed
+//!   if they are at the top of the bump region, otherwise they are leaked.
+//! - Fresh memory is never read or initialized, `alloc_zeroed` skips zeroing it as grown WASM memory is zero.
+//!
+//! Opt in by calling [ksplang_global_allocator!](crate::ksplang_global_allocator) in a solution crate.
+//!
+//! Status: no solution opts in yet. The goal of shorter ksplang traces for allocation-heavy days (5-2 first)
+//! is not shown, as nobody has compared their translated instruction counts with and without it yet.
+//! Enable it in a solution only together with those numbers.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::UnsafeCell;
+use crate::{_read_raw_i64, _save_raw_i64};
+
+/// Largest size served from the size class free lists.
+pub const MAX_SMALL_SIZE: usize = 4096;
+/// Number of size classes, sizes are 8, 16, ..., [MAX_SMALL_SIZE].
+const SIZE_CLASSES: usize = 10;
+/// Largest alignment of small blocks, blocks with larger alignment are allocated as large ones.
+const MAX_SMALL_ALIGN: usize = 16;
+/// Memory is requested from the host in chunks of at least this many bytes.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Raw i64 cells with bookkeeping, offsets into BOOKKEEPING.
+/// Next free address of the bump region.
+const TOP: usize = 0;
+/// End of the bump region.
+const END: usize = 1;
+/// Highest address of the bump region ever handed out, everything above is fresh memory.
+const PEAK: usize = 2;
+/// Total bytes requested from the host.
+const HEAP_SIZE: usize = 3;
+/// Heads of the free lists, one per size class, 0 is an empty list.
+const FREE_LISTS: usize = 4;
+const BOOKKEEPING_CELLS: usize = FREE_LISTS + SIZE_CLASSES;
+
+struct Bookkeeping(UnsafeCell<[u8; BOOKKEEPING_CELLS]>);
+
+// WASM is single-threaded. Natively, raw cells are thread local, so each thread gets its own state.
+unsafe impl Sync for Bookkeeping {}
+
+/// Zero-initialized bytes are also raw i64 cells with the value 0, so this needs no initialization.
+static BOOKKEEPING: Bookkeeping = Bookkeeping(UnsafeCell::new([0; BOOKKEEPING_CELLS]));
+
+fn read_cell(cell: usize) -> usize {
+    unsafe { _read_raw_i64(BOOKKEEPING.0.get() as usize + cell) as usize }
+}
+
+fn save_cell(cell: usize, value: usize) {
+    unsafe { _save_raw_i64(value as i64, BOOKKEEPING.0.get() as usize + cell) }
+}
+
+/// Bump and size class allocator with bookkeeping in raw i64 cells, see the [module docs](self).
+///
+/// Natively, it is usable for tests only: the state lives in the native raw memory,
+/// which is cleared by [set_input_values](crate::native::set_input_values).
+pub struct KsplangAllocator;
+
+impl KsplangAllocator {
+    /// Returns the most memory (in bytes) ever handed out by this allocator, including size class rounding.
+    pub fn high_water_mark() -> usize {
+        read_cell(HEAP_SIZE) - (read_cell(END) - read_cell(PEAK))
+    }
+
+    /// Returns the number of bytes requested from the host.
+    pub fn heap_size() -> usize {
+        read_cell(HEAP_SIZE)
+    }
+}
+
+/// Returns the size class index for a small allocation.
+fn size_class(size: usize) -> usize {
+    if size <= 8 {
+        0
+    } else {
+        // ceil(log2(size)) - 3
+        (usize::BITS - (size - 1).leading_zeros()) as usize - 3
+    }
+}
+
+/// The size class of a small layout. Blocks of a class are aligned to `min(class size, MAX_SMALL_ALIGN)`,
+/// so the alignment counts as a minimum size.
+fn layout_class(layout: &Layout) -> usize {
+    size_class(layout.size().max(layout.align()))
+}
+
+fn is_small(layout: &Layout) -> bool {
+    layout.size() <= MAX_SMALL_SIZE && layout.align() <= MAX_SMALL_ALIGN
+}
+
+/// Allocates fresh memory from the bump region, returns the block and whether it was never used before.
+unsafe fn bump(size: usize, align: usize) -> (*mut u8, bool) {
+    let mut start = align_up(read_cell(TOP), align);
+    let mut end = read_cell(END);
+    if start == 0 || start > end || size > end - start {
+        let Some((chunk, chunk_size)) = grow(size + align) else {
+            return (std::ptr::null_mut(), false);
+        };
+        save_cell(HEAP_SIZE, read_cell(HEAP_SIZE) + chunk_size);
+        if chunk != end || start == 0 {
+            // Not contiguous with the current region, the rest of it is abandoned
+            save_cell(PEAK, chunk);
+            start = align_up(chunk, align);
+        }
+        end = chunk + chunk_size;
+        save_cell(END, end);
+    }
+    let new_top = start + size;
+    save_cell(TOP, new_top);
+    let peak = read_cell(PEAK);
+    let fresh = start >= peak;
+    if new_top > peak {
+        save_cell(PEAK, new_top);
+    }
+    (start as *mut u8, fresh)
+}
+
+fn align_up(address: usize, align: usize) -> usize {
+    (address + align - 1) & !(align - 1)
+}
+
+/// Requests at least `size` bytes of zeroed memory from the host, returns its address and size.
+#[cfg(target_arch = "wasm32")]
+fn grow(size: usize) -> Option<(usize, usize)> {
+    const PAGE_SIZE: usize = 64 * 1024;
+    let pages = size.max(CHUNK_SIZE).div_ceil(PAGE_SIZE);
+    let previous = core::arch::wasm32::memory_grow(0, pages);
+    if previous == usize::MAX {
+        return None;
+    }
+    Some((previous * PAGE_SIZE, pages * PAGE_SIZE))
+}
+
+/// Requests at least `size` bytes of zeroed memory from the host, returns its address and size.
+#[cfg(not(target_arch = "wasm32"))]
+fn grow(size: usize) -> Option<(usize, usize)> {
+    let size = size.max(CHUNK_SIZE);
+    let chunk = unsafe { std::alloc::System.alloc_zeroed(Layout::from_size_align(size, MAX_SMALL_ALIGN).ok()?) };
+    if chunk.is_null() { None } else { Some((chunk as usize, size)) }
+}
+
+unsafe impl GlobalAlloc for KsplangAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { allocate(layout).0 }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let (ptr, fresh) = unsafe { allocate(layout) };
+        if !ptr.is_null() && !fresh {
+            unsafe { std::ptr::write_bytes(ptr, 0, layout.size()) };
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if is_small(&layout) {
+            let list = FREE_LISTS + layout_class(&layout);
+            unsafe { _save_raw_i64(read_cell(list) as i64, ptr as usize) };
+            save_cell(list, ptr as usize);
+        } else if ptr as usize + layout.size() == read_cell(TOP) {
+            save_cell(TOP, ptr as usize);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        if is_small(&layout) && is_small(&new_layout) && layout_class(&layout) == layout_class(&new_layout) {
+            return ptr;
+        }
+        let new_ptr = unsafe { allocate(new_layout).0 };
+        if !new_ptr.is_null() {
+            unsafe {
+                std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Returns the block and whether it is fresh (zeroed) memory.
+unsafe fn allocate(layout: Layout) -> (*mut u8, bool) {
+    if !is_small(&layout) {
+        return unsafe { bump(layout.size(), layout.align()) };
+    }
+    let class = layout_class(&layout);
+    let list = FREE_LISTS + class;
+    let head = read_cell(list);
+    if head != 0 {
+        // The link is the only raw value in a free block, it has to become a byte again
+        let next = unsafe { _read_raw_i64(head) } as usize;
+        unsafe { _save_raw_i64(0, head) };
+        save_cell(list, next);
+        return (head as *mut u8, false);
+    }
+    let class_size = 8 << class;
+    unsafe { bump(class_size, class_size.min(MAX_SMALL_ALIGN)) }
+}
+
+/// Makes [KsplangAllocator] the global allocator of the crate when compiled to WASM.
+///
+/// Natively, the default allocator is kept.
+#[macro_export]
+macro_rules! ksplang_global_allocator {
+    () => {
+        #[cfg(target_arch = "wasm32")]
+        #[global_allocator]
+        static KSPLANG_ALLOCATOR: $crate::allocator::KsplangAllocator = $crate::allocator::KsplangAllocator;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_classes() {
+        assert_eq!(size_class(1), 0);
+        assert_eq!(size_class(8), 0);
+        assert_eq!(size_class(9), 1);
+        assert_eq!(size_class(16), 1);
+        assert_eq!(size_class(17), 2);
+        assert_eq!(size_class(MAX_SMALL_SIZE), SIZE_CLASSES - 1);
+    }
+
+    #[test]
+    fn allocations_are_reused() {
+        let allocator = KsplangAllocator;
+        let small = Layout::from_size_align(24, 8).unwrap();
+        let large = Layout::from_size_align(10_000, 8).unwrap();
+        unsafe {
+            let a = allocator.alloc(small);
+            let b = allocator.alloc_zeroed(small);
+            assert_eq!(a as usize % 8, 0);
+            assert_ne!(a, b);
+            assert!(std::slice::from_raw_parts(b, 24).iter().all(|&x| x == 0));
+            std::ptr::write_bytes(a, 0xAB, 24);
+
+            allocator.dealloc(a, small);
+            let c = allocator.alloc_zeroed(small);
+            assert_eq!(c, a);
+            assert_eq!(_read_raw_i64(c as usize), 0);
+            assert!(std::slice::from_raw_parts(c.add(1), 23).iter().all(|&x| x == 0));
+
+            let grown = allocator.realloc(c, small, 32);
+            assert_eq!(grown, c);
+
+            let big = allocator.alloc(large);
+            let high_water = KsplangAllocator::high_water_mark();
+            assert!(high_water >= 10_000 + 64);
+            allocator.dealloc(big, large);
+            assert_eq!(allocator.alloc(large), big);
+            assert_eq!(KsplangAllocator::high_water_mark(), high_water);
+            assert!(KsplangAllocator::heap_size() >= high_water);
+        }
+    }
+
+    #[test]
+    fn small_blocks_respect_alignment() {
+        let allocator = KsplangAllocator;
+        let unaligned = Layout::from_size_align(8, 8).unwrap();
+        let aligned = Layout::from_size_align(8, 16).unwrap();
+        unsafe {
+            // Leaves an 8-aligned block in the free list of the smallest class
+            let blocks: Vec<_> = (0..3).map(|_| allocator.alloc(unaligned)).collect();
+            for &block in &blocks {
+                allocator.dealloc(block, unaligned);
+            }
+            for _ in 0..3 {
+                assert_eq!(allocator.alloc(aligned) as usize % 16, 0);
+            }
+        }
+    }
+}
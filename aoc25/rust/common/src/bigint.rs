@@ -0,0 +1,429 @@
+//! Arbitrary-precision integers for answers which do not fit into i64.
+//!
+//! Every ksplang overflow is fatal, so a [BigInt] keeps its magnitude in limbs of base [BASE] (10^9)
+//! in raw i64 memory, least significant first. A product of two limbs is below 10^18, so all of
+//! the arithmetic is done with the cheap (crashing) instructions without ever overflowing.
+//! Decimal digits are just the digits of the limbs, so exporting needs no big divisions,
+//! see [Output::big_int](crate::output::Output::big_int).
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+use crate::failure;
+use crate::instructions::{add_unchecked, cmp, div_unchecked, is_nonzero, is_zero, lensum, mul_unchecked, negate_unchecked, rem, sgn};
+use crate::raw_vec::RawFastVec;
+
+/// The base of the limbs.
+pub const BASE: i64 = 1_000_000_000;
+/// Number of decimal digits in a full limb.
+const BASE_DIGITS: u32 = 9;
+
+/// A signed integer of any size, see the [module docs](self).
+///
+/// There are no leading zero limbs, zero has no limbs and is never negative.
+#[derive(Clone, Default)]
+pub struct BigInt {
+    limbs: RawFastVec,
+    negative: bool,
+}
+
+impl BigInt {
+    /// Creates a zero.
+    pub fn new() -> Self {
+        BigInt { limbs: RawFastVec::new(), negative: false }
+    }
+
+    /// Creates a BigInt with the value, this is safe for all inputs.
+    pub fn from_i64(value: i64) -> Self {
+        let mut result = BigInt::new();
+        result.negative = sgn(value) == -1;
+        // Working with the (possibly negative) value directly, |i64::MIN| does not fit
+        let mut rest = value;
+        while is_nonzero(rest) {
+            let limb = rem(rest, BASE);
+            result.limbs.push(if sgn(limb) == -1 { negate_unchecked(limb) } else { limb });
+            rest = div_unchecked(rest, BASE);
+        }
+        result
+    }
+
+    /// Converts the value back, returns None if it does not fit into i64.
+    pub fn to_i64(&self) -> Option<i64> {
+        // Accumulating a negative value, so i64::MIN fits
+        let mut result: i64 = 0;
+        for i in (0..self.limbs.len()).rev() {
+            let scaled = crate::instructions::checked_mul(result, BASE)?.to_i64();
+            result = crate::instructions::checked_sub(scaled, unsafe { self.limbs.get_unchecked(i) })?.to_i64();
+        }
+        if self.negative { Some(result) } else { crate::instructions::checked_neg(result).map(|x| x.to_i64()) }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns -1, 0 or 1 depending on the sign.
+    pub fn signum(&self) -> i32 {
+        if self.negative { -1 } else if self.is_zero() { 0 } else { 1 }
+    }
+
+    /// Negates the value in place.
+    pub fn negate(&mut self) {
+        self.negative = !self.negative && !self.is_zero();
+    }
+
+    /// Adds a small value, this is safe for all inputs.
+    pub fn add_small(&mut self, value: i64) {
+        *self += &BigInt::from_i64(value);
+    }
+
+    /// Multiplies by a small value in place.
+    ///
+    /// # Safety
+    /// Fails with [INVALID_ARGUMENT](crate::failure::INVALID_ARGUMENT) if `|factor| > BASE`.
+    pub fn mul_small(&mut self, factor: i64) {
+        let factor_negative = sgn(factor) == -1;
+        let factor = small_magnitude(factor);
+        if is_zero(factor) {
+            *self = BigInt::new();
+            return;
+        }
+        let mut carry = 0;
+        for i in 0..self.limbs.len() {
+            // Below 10^9 * 10^9 + 10^9
+            let value = add_unchecked(mul_unchecked(unsafe { self.limbs.get_unchecked(i) }, factor), carry);
+            self.limbs.set(i, rem(value, BASE));
+            carry = div_unchecked(value, BASE);
+        }
+        while is_nonzero(carry) {
+            self.limbs.push(rem(carry, BASE));
+            carry = div_unchecked(carry, BASE);
+        }
+        if factor_negative {
+            self.negate();
+        }
+    }
+
+    /// Divides by a small value in place, rounding towards zero, and returns the remainder.
+    /// The remainder has the sign of the dividend, like the `/` and `%` operators.
+    ///
+    /// # Safety
+    /// Fails with [INVALID_ARGUMENT](crate::failure::INVALID_ARGUMENT) if `|divisor| > BASE`,
+    /// and crashes the program if the divisor is 0.
+    pub fn divmod_small(&mut self, divisor: i64) -> i64 {
+        let divisor_negative = sgn(divisor) == -1;
+        let divisor = small_magnitude(divisor);
+        let mut remainder = 0;
+        for i in (0..self.limbs.len()).rev() {
+            // Below BASE * BASE
+            let value = add_unchecked(mul_unchecked(remainder, BASE), unsafe { self.limbs.get_unchecked(i) });
+            self.limbs.set(i, div_unchecked(value, divisor));
+            remainder = rem(value, divisor);
+        }
+        let dividend_negative = self.negative;
+        self.trim();
+        self.negative = dividend_negative != divisor_negative && !self.is_zero();
+        if dividend_negative { negate_unchecked(remainder) } else { remainder }
+    }
+
+    /// Returns the decimal digits of the absolute value, most significant first. Zero is `[0]`.
+    pub fn decimal_digits(&self) -> Vec<i64> {
+        let Some(top) = self.limbs.last() else {
+            return vec![0];
+        };
+        let mut digits = Vec::new();
+        push_limb_digits(&mut digits, top, lensum(top, 0));
+        for i in (0..self.limbs.len() - 1).rev() {
+            push_limb_digits(&mut digits, unsafe { self.limbs.get_unchecked(i) }, BASE_DIGITS);
+        }
+        digits
+    }
+
+    fn add_signed(&mut self, other: &BigInt, other_negative: bool) {
+        if self.negative == other_negative {
+            add_magnitude(&mut self.limbs, &other.limbs);
+            self.negative = other_negative && !self.is_zero();
+            return;
+        }
+        match cmp_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Equal => *self = BigInt::new(),
+            Ordering::Greater => {
+                sub_magnitude(&mut self.limbs, &other.limbs);
+                self.trim();
+            }
+            Ordering::Less => {
+                let mut limbs = other.limbs.clone();
+                sub_magnitude(&mut limbs, &self.limbs);
+                self.limbs = limbs;
+                self.negative = other_negative;
+                self.trim();
+            }
+        }
+    }
+
+    /// Removes leading zero limbs.
+    fn trim(&mut self) {
+        while self.limbs.last().is_some_and(is_zero) {
+            self.limbs.pop();
+        }
+        if self.is_zero() {
+            self.negative = false;
+        }
+    }
+}
+
+/// Returns `|value|`, failing if it is larger than [BASE].
+fn small_magnitude(value: i64) -> i64 {
+    // cmp is safe even for i64::MIN
+    crate::ensure!(cmp(value, negate_unchecked(BASE)) != -1 && cmp(value, BASE) != 1, failure::INVALID_ARGUMENT);
+    if sgn(value) == -1 { negate_unchecked(value) } else { value }
+}
+
+/// Pushes the last `count` decimal digits of the limb, most significant first.
+fn push_limb_digits(digits: &mut Vec<i64>, limb: i64, count: u32) {
+    let start = digits.len();
+    let mut rest = limb;
+    for _ in 0..count {
+        digits.push(rem(rest, 10));
+        rest = div_unchecked(rest, 10);
+    }
+    digits[start..].reverse();
+}
+
+fn cmp_magnitude(a: &RawFastVec, b: &RawFastVec) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for i in (0..a.len()).rev() {
+        match cmp(unsafe { a.get_unchecked(i) }, unsafe { b.get_unchecked(i) }) {
+            0 => {}
+            1 => return Ordering::Greater,
+            _ => return Ordering::Less,
+        }
+    }
+    Ordering::Equal
+}
+
+/// `a += b`, for magnitudes.
+fn add_magnitude(a: &mut RawFastVec, b: &RawFastVec) {
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let value = add_unchecked(add_unchecked(a.get(i).unwrap_or(0), b.get(i).unwrap_or(0)), carry);
+        let wrapped = add_unchecked(value, -BASE);
+        carry = if sgn(wrapped) == -1 { 0 } else { 1 };
+        let limb = if is_zero(carry) { value } else { wrapped };
+        if i < a.len() { a.set(i, limb) } else { a.push(limb) }
+    }
+    if is_nonzero(carry) {
+        a.push(carry);
+    }
+}
+
+/// `a -= b`, for magnitudes where `a >= b`. Leaves leading zeros.
+fn sub_magnitude(a: &mut RawFastVec, b: &RawFastVec) {
+    let mut borrow = 0;
+    for i in 0..a.len() {
+        if i >= b.len() && is_zero(borrow) {
+            break;
+        }
+        let value = add_unchecked(unsafe { a.get_unchecked(i) }, negate_unchecked(add_unchecked(b.get(i).unwrap_or(0), borrow)));
+        borrow = if sgn(value) == -1 { 1 } else { 0 };
+        a.set(i, if is_zero(borrow) { value } else { add_unchecked(value, BASE) });
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        BigInt::from_i64(value)
+    }
+}
+
+impl ops::AddAssign<&BigInt> for BigInt {
+    fn add_assign(&mut self, other: &BigInt) {
+        self.add_signed(other, other.negative);
+    }
+}
+
+impl ops::SubAssign<&BigInt> for BigInt {
+    fn sub_assign(&mut self, other: &BigInt) {
+        self.add_signed(other, !other.negative && !other.is_zero());
+    }
+}
+
+impl ops::MulAssign<&BigInt> for BigInt {
+    fn mul_assign(&mut self, other: &BigInt) {
+        *self = &*self * other;
+    }
+}
+
+impl ops::Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        let mut result = self.clone();
+        result += other;
+        result
+    }
+}
+
+impl ops::Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        let mut result = self.clone();
+        result -= other;
+        result
+    }
+}
+
+/// Schoolbook multiplication, every step is below 10^18 + 2 * 10^9.
+impl ops::Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        if self.is_zero() || other.is_zero() {
+            return BigInt::new();
+        }
+        let (a, b) = (&self.limbs, &other.limbs);
+        let mut limbs: RawFastVec = std::iter::repeat_n(0, a.len() + b.len()).collect();
+        for i in 0..a.len() {
+            let mut carry = 0;
+            let a_limb = unsafe { a.get_unchecked(i) };
+            for j in 0..b.len() {
+                let value = add_unchecked(add_unchecked(unsafe { limbs.get_unchecked(i + j) }, mul_unchecked(a_limb, unsafe { b.get_unchecked(j) })), carry);
+                limbs.set(i + j, rem(value, BASE));
+                carry = div_unchecked(value, BASE);
+            }
+            limbs.set(i + b.len(), carry);
+        }
+        let mut result = BigInt { limbs, negative: self.negative != other.negative };
+        result.trim();
+        result
+    }
+}
+
+impl ops::Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(mut self) -> BigInt {
+        self.negate();
+        self
+    }
+}
+
+impl PartialEq for BigInt {
+    fn eq(&self, other: &BigInt) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BigInt {}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.limbs, &other.limbs),
+            (true, true) => cmp_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        for digit in self.decimal_digits() {
+            write!(f, "{digit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BigInt({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factorial(n: i64) -> BigInt {
+        let mut result = BigInt::from(1);
+        for i in 2..=n {
+            result.mul_small(i);
+        }
+        result
+    }
+
+    #[test]
+    fn conversions() {
+        for value in [0, 1, -1, 999_999_999, 1_000_000_000, -1_000_000_001, i64::MAX, i64::MIN] {
+            let big = BigInt::from(value);
+            assert_eq!(big.to_string(), value.to_string());
+            assert_eq!(big.to_i64(), Some(value));
+        }
+        let mut too_big = BigInt::from(i64::MAX);
+        too_big.add_small(1);
+        assert_eq!(too_big.to_string(), "9223372036854775808");
+        assert_eq!(too_big.to_i64(), None);
+        assert_eq!((-too_big).to_i64(), Some(i64::MIN));
+        assert_eq!(BigInt::new().decimal_digits(), vec![0]);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let f30 = factorial(30);
+        assert_eq!(f30.to_string(), "265252859812191058636308480000000");
+        assert_eq!((&f30 * &factorial(20)).to_string(), "645334215311676394593146071296945369907200000000000");
+        assert_eq!((&f30 * &-BigInt::from(1)).to_string(), "-265252859812191058636308480000000");
+
+        let a = BigInt::from(1_000_000_000_000);
+        let b = BigInt::from(-999_999_999_999_999);
+        assert_eq!((&a + &b).to_string(), "-998999999999999");
+        assert_eq!((&b - &a).to_string(), "-1000999999999999");
+        assert_eq!((&a - &a).signum(), 0);
+        assert!(!(&b - &b).is_negative());
+
+        let mut x = f30.clone();
+        assert_eq!(x.divmod_small(7), 0);
+        assert_eq!(x.divmod_small(1_000_000_000), 640_000_000);
+        assert_eq!(x.to_string(), "37893265687455865519472");
+        let mut y = BigInt::from(-17);
+        assert_eq!(y.divmod_small(-5), -2);
+        assert_eq!(y.to_i64(), Some(3));
+        let mut z = BigInt::from(5);
+        z.mul_small(-1_000_000_000);
+        assert_eq!(z.to_i64(), Some(-5_000_000_000));
+    }
+
+    #[test]
+    fn ordering() {
+        let values = [BigInt::from(-5), factorial(25), BigInt::new(), -factorial(25), BigInt::from(3)];
+        let mut sorted = values.to_vec();
+        sorted.sort();
+        let strings: Vec<String> = sorted.iter().map(|x| x.to_string()).collect();
+        assert_eq!(strings, vec!["-15511210043330985984000000", "-5", "0", "3", "15511210043330985984000000"]);
+        assert_eq!(factorial(25), factorial(25));
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -4")]
+    fn large_factor_fails() {
+        BigInt::from(1).mul_small(BASE + 1);
+    }
+}
@@ -0,0 +1,224 @@
+//! Compact boolean storage in raw i64 cells.
+//!
+//! A `Vec<bool>` costs a full stack cell per value. [RawBitSet] packs [BITS_PER_CELL] bits into every
+//! raw cell instead. Only the low 62 bits are used, so every bit value (up to `1 << 61`) and every word
+//! stays positive, and all the operations (and, bitshift, div and additions of disjoint bits) can never overflow.
+
+use crate::instructions::{add_unchecked, and, bitnot, bitor, bitshift, div_unchecked, is_nonzero, is_zero, negate_unchecked};
+use crate::raw_vec::RawFastVec;
+
+/// Number of bits stored in a single raw cell.
+pub const BITS_PER_CELL: usize = 62;
+
+/// A fixed-size set of bits, see the [module docs](self).
+#[derive(Clone)]
+pub struct RawBitSet {
+    words: RawFastVec,
+    len: usize,
+}
+
+impl RawBitSet {
+    /// Creates a bit set of `len` bits, all cleared.
+    pub fn new(len: usize) -> Self {
+        let words = std::iter::repeat_n(0, len.div_ceil(BITS_PER_CELL)).collect();
+        RawBitSet { words, len }
+    }
+
+    /// Returns the number of bits (set or not).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks whether the bit is set. Panics if out of bounds.
+    pub fn test(&self, index: usize) -> bool {
+        let (word, mask) = self.position(index);
+        is_nonzero(and(unsafe { self.words.get_unchecked(word) }, mask))
+    }
+
+    /// Sets the bit, returns true if it was not set before. Panics if out of bounds.
+    pub fn set(&mut self, index: usize) -> bool {
+        let (word, mask) = self.position(index);
+        let value = unsafe { self.words.get_unchecked(word) };
+        if is_nonzero(and(value, mask)) {
+            return false;
+        }
+        self.words.set(word, add_unchecked(value, mask));
+        true
+    }
+
+    /// Clears the bit, returns true if it was set before. Panics if out of bounds.
+    pub fn clear(&mut self, index: usize) -> bool {
+        let (word, mask) = self.position(index);
+        let value = unsafe { self.words.get_unchecked(word) };
+        if is_zero(and(value, mask)) {
+            return false;
+        }
+        self.words.set(word, add_unchecked(value, negate_unchecked(mask)));
+        true
+    }
+
+    /// Flips the bit, returns its new value. Panics if out of bounds.
+    pub fn toggle(&mut self, index: usize) -> bool {
+        if self.clear(index) {
+            false
+        } else {
+            self.set(index)
+        }
+    }
+
+    /// Clears all bits.
+    pub fn clear_all(&mut self) {
+        self.words.as_mut_slice().fill(0);
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(popcount).sum()
+    }
+
+    /// Checks whether no bit is set.
+    pub fn none(&self) -> bool {
+        self.words.iter().all(is_zero)
+    }
+
+    /// Sets all bits which are set in `other`. Panics if the sizes differ.
+    pub fn union_with(&mut self, other: &RawBitSet) {
+        self.combine(other, bitor);
+    }
+
+    /// Clears all bits which are not set in `other`. Panics if the sizes differ.
+    pub fn intersect_with(&mut self, other: &RawBitSet) {
+        self.combine(other, and);
+    }
+
+    /// Clears all bits which are set in `other`. Panics if the sizes differ.
+    pub fn difference_with(&mut self, other: &RawBitSet) {
+        self.combine(other, |a, b| and(a, bitnot(b)));
+    }
+
+    /// Iterates over the indices of set bits in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, word)| {
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                if is_zero(rest) {
+                    return None;
+                }
+                // The lowest set bit, two's complement negation only keeps that one
+                let lowest = and(rest, negate_unchecked(rest));
+                rest = add_unchecked(rest, negate_unchecked(lowest));
+                Some(word_index * BITS_PER_CELL + bit_position(lowest) as usize)
+            })
+        })
+    }
+
+    fn combine(&mut self, other: &RawBitSet, op: impl Fn(i64, i64) -> i64) {
+        assert_eq!(self.len, other.len, "RawBitSet sizes differ");
+        for i in 0..self.words.len() {
+            self.words.set(i, op(unsafe { self.words.get_unchecked(i) }, unsafe { other.words.get_unchecked(i) }));
+        }
+    }
+
+    /// Returns the word index and the mask of the bit.
+    fn position(&self, index: usize) -> (usize, i64) {
+        assert!(index < self.len, "Index out of bounds");
+        (index / BITS_PER_CELL, bitshift(1, (index % BITS_PER_CELL) as i64))
+    }
+}
+
+/// Counts the set bits of a non-negative word by repeatedly clearing the lowest one.
+fn popcount(word: i64) -> usize {
+    let mut rest = word;
+    let mut count = 0;
+    while is_nonzero(rest) {
+        rest = and(rest, add_unchecked(rest, -1));
+        count += 1;
+    }
+    count
+}
+
+/// Returns `log2(bit)` for a single bit below `1 << 62`, a binary search with div.
+fn bit_position(bit: i64) -> u32 {
+    let mut rest = bit;
+    let mut position = 0;
+    for step in [32, 16, 8, 4, 2, 1] {
+        let shifted = div_unchecked(rest, bitshift(1, step));
+        if is_nonzero(shifted) {
+            rest = shifted;
+            position += step as u32;
+        }
+    }
+    position
+}
+
+impl std::fmt::Debug for RawBitSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_bool_vec() {
+        let len = 200;
+        let mut bits = RawBitSet::new(len);
+        let mut expected = vec![false; len];
+        for i in 0..1000 {
+            let index = i * 37 % len;
+            match i % 4 {
+                0 | 1 => assert_eq!(bits.set(index), !std::mem::replace(&mut expected[index], true)),
+                2 => assert_eq!(bits.clear(index), std::mem::replace(&mut expected[index], false)),
+                _ => {
+                    expected[index] = !expected[index];
+                    assert_eq!(bits.toggle(index), expected[index]);
+                }
+            }
+        }
+        for (index, &value) in expected.iter().enumerate() {
+            assert_eq!(bits.test(index), value);
+        }
+        let set: Vec<usize> = (0..len).filter(|&i| expected[i]).collect();
+        assert_eq!(bits.iter().collect::<Vec<_>>(), set);
+        assert_eq!(bits.count_ones(), set.len());
+    }
+
+    #[test]
+    fn set_operations() {
+        let len = 130;
+        let mut a = RawBitSet::new(len);
+        let mut b = RawBitSet::new(len);
+        for i in [0, 61, 62, 63, 123, 129] {
+            a.set(i);
+        }
+        for i in [1, 61, 63, 100, 129] {
+            b.set(i);
+        }
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!(union.iter().collect::<Vec<_>>(), vec![0, 1, 61, 62, 63, 100, 123, 129]);
+        let mut intersection = a.clone();
+        intersection.intersect_with(&b);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), vec![61, 63, 129]);
+        a.difference_with(&b);
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![0, 62, 123]);
+        assert_eq!(format!("{a:?}"), "{0, 62, 123}");
+
+        a.clear_all();
+        assert!(a.none() && !b.none());
+        assert_eq!(popcount((1 << 62) - 1), 62);
+        assert_eq!(bit_position(1 << 61), 61);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn out_of_bounds() {
+        RawBitSet::new(62).set(62);
+    }
+}
@@ -0,0 +1,13 @@
+//! Collections stored in raw i64 memory.
+//!
+//! The std collections keep their data in byte-based WASM memory and use generic i64 arithmetic
+//! (hashing with wrapping multiplications, comparisons), which is very expensive after translation.
+//! These store every value in a single raw cell and only use cheap crash-free instructions.
+
+mod dsu;
+mod hash_map;
+mod heap;
+
+pub use dsu::RawDsu;
+pub use hash_map::{RawHashMap, RawHashSet};
+pub use heap::RawHeap;
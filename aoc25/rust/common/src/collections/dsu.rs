@@ -0,0 +1,124 @@
+use crate::instructions::{add_unchecked, is_nonzero, is_zero, lt, subabs_unchecked};
+use crate::raw_vec::RawFastVec;
+
+/// A disjoint set union (union-find) of the elements `0..len`, with path compression and union by size.
+///
+/// The parent and size of every element are single raw cells.
+#[derive(Clone, Debug)]
+pub struct RawDsu {
+    parents: RawFastVec,
+    /// Only valid for roots.
+    sizes: RawFastVec,
+    components: usize,
+}
+
+impl RawDsu {
+    /// Creates `len` single-element components.
+    pub fn new(len: usize) -> Self {
+        RawDsu {
+            parents: (0..len as i64).collect(),
+            sizes: std::iter::repeat_n(1, len).collect(),
+            components: len,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Returns the representative of the component of `x`. Panics if out of bounds.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x as i64;
+        loop {
+            let parent = self.parents[root as usize].get();
+            if is_zero(subabs_unchecked(parent, root)) {
+                break;
+            }
+            root = parent;
+        }
+        // Path compression, everything on the way now points directly to the root
+        let mut current = x as i64;
+        while is_nonzero(subabs_unchecked(current, root)) {
+            let parent = unsafe { self.parents.get_unchecked(current as usize) };
+            self.parents.set(current as usize, root);
+            current = parent;
+        }
+        root as usize
+    }
+
+    /// Merges the components of `a` and `b`, returns false if they already were the same component.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+        let size_a = unsafe { self.sizes.get_unchecked(a) };
+        let size_b = unsafe { self.sizes.get_unchecked(b) };
+        // The smaller component goes under the larger one
+        let (root, child) = if lt(size_a, size_b) { (b, a) } else { (a, b) };
+        self.parents.set(child, root as i64);
+        self.sizes.set(root, add_unchecked(size_a, size_b));
+        self.components -= 1;
+        true
+    }
+
+    /// Checks whether `a` and `b` are in the same component.
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the size of the component of `x`.
+    pub fn size_of(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        unsafe { self.sizes.get_unchecked(root) as usize }
+    }
+
+    /// Returns the number of components.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// Returns the sizes of all components, in the order of their representatives.
+    pub fn component_sizes(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&x| is_zero(subabs_unchecked(unsafe { self.parents.get_unchecked(x) }, x as i64)))
+            .map(|root| unsafe { self.sizes.get_unchecked(root) } as usize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn components() {
+        let mut dsu = RawDsu::new(10);
+        assert_eq!(dsu.component_count(), 10);
+        assert!(dsu.union(0, 1));
+        assert!(dsu.union(2, 3));
+        assert!(dsu.union(1, 3));
+        assert!(!dsu.union(0, 2));
+        assert!(dsu.union(7, 8));
+        // A long chain to exercise path compression
+        for i in 4..7 {
+            dsu.union(i, i + 1);
+        }
+        assert_eq!(dsu.component_count(), 3);
+        assert!(dsu.same(4, 8));
+        assert!(!dsu.same(0, 9));
+        assert_eq!(dsu.size_of(3), 4);
+        assert_eq!(dsu.size_of(9), 1);
+
+        let mut sizes = dsu.component_sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 4, 5]);
+        assert_eq!(sizes.iter().sum::<usize>(), dsu.len());
+    }
+}
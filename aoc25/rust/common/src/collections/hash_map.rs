@@ -0,0 +1,353 @@
+use crate::instructions::{eq, euclid_mod, is_nonzero, is_zero};
+use crate::raw_vec::RawFastVec;
+
+/// Table sizes, primes which roughly double. The hash of a key is simply `key mod size`,
+/// which never crashes and spreads out structured keys (e.g. `y * width + x`) well thanks to the prime size.
+const SIZES: [usize; 28] = [
+    7, 17, 37, 79, 163, 331, 673, 1361, 2729, 5471, 10949, 21911, 43853, 87719, 175447, 350899,
+    701819, 1403641, 2807303, 5614657, 11229331, 22458671, 44917381, 89834777, 179669557,
+    359339171, 718678369, 1437356741,
+];
+
+/// A hash map from i64 keys to i64 values, the raw memory equivalent of `HashMap<i64, i64>`.
+///
+/// Open addressing with linear probing, the table grows to the next size once it is 3/4 full.
+/// Removal shifts the following entries back, so there are no tombstones.
+#[derive(Clone, Default)]
+pub struct RawHashMap {
+    keys: RawFastVec,
+    values: RawFastVec,
+    /// 1 if the slot is used, 0 otherwise.
+    occupied: RawFastVec,
+    len: usize,
+    /// Index into [SIZES] of the current size, only meaningful if allocated.
+    size_index: usize,
+}
+
+impl RawHashMap {
+    /// Creates an empty map, does not allocate.
+    pub fn new() -> Self {
+        RawHashMap { keys: RawFastVec::new(), values: RawFastVec::new(), occupied: RawFastVec::new(), len: 0, size_index: 0 }
+    }
+
+    /// Creates a map which can hold at least `capacity` entries without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut map = Self::new();
+        if capacity > 0 {
+            map.allocate(size_index_for(capacity));
+        }
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of entries the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        max_len(self.slots())
+    }
+
+    /// Inserts the value, returns the previous value of the key.
+    pub fn insert(&mut self, key: i64, value: i64) -> Option<i64> {
+        let slot = self.find_slot_for_insert(key);
+        let previous = if is_nonzero(unsafe { self.occupied.get_unchecked(slot) }) {
+            Some(unsafe { self.values.get_unchecked(slot) })
+        } else {
+            self.occupy(slot, key);
+            None
+        };
+        self.values.set(slot, value);
+        previous
+    }
+
+    pub fn get(&self, key: i64) -> Option<i64> {
+        self.find(key).map(|slot| unsafe { self.values.get_unchecked(slot) })
+    }
+
+    /// Returns the value of the key, or `default` if it is not in the map.
+    pub fn get_or(&self, key: i64, default: i64) -> i64 {
+        self.get(key).unwrap_or(default)
+    }
+
+    pub fn contains_key(&self, key: i64) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Adds `delta` to the value of the key (missing keys start at 0), returns the new value.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the value overflows i64.
+    pub fn add(&mut self, key: i64, delta: i64) -> i64 {
+        let slot = self.find_slot_for_insert(key);
+        let value = if is_nonzero(unsafe { self.occupied.get_unchecked(slot) }) {
+            crate::instructions::add_unchecked(unsafe { self.values.get_unchecked(slot) }, delta)
+        } else {
+            self.occupy(slot, key);
+            delta
+        };
+        self.values.set(slot, value);
+        value
+    }
+
+    /// Removes the key, returns its value if it was in the map.
+    pub fn remove(&mut self, key: i64) -> Option<i64> {
+        let mut hole = self.find(key)?;
+        let value = unsafe { self.values.get_unchecked(hole) };
+        self.occupied.set(hole, 0);
+        self.len -= 1;
+
+        // Move back entries of the probe sequence which would not be found across the hole
+        let slots = self.slots();
+        let mut slot = hole;
+        loop {
+            slot = next_slot(slot, slots);
+            if is_zero(unsafe { self.occupied.get_unchecked(slot) }) {
+                break;
+            }
+            let home = self.home_slot(unsafe { self.keys.get_unchecked(slot) });
+            // The entry can move if its home is not cyclically within (hole, slot]
+            let stays = if hole <= slot { hole < home && home <= slot } else { hole < home || home <= slot };
+            if !stays {
+                self.keys.set(hole, unsafe { self.keys.get_unchecked(slot) });
+                self.values.set(hole, unsafe { self.values.get_unchecked(slot) });
+                self.occupied.set(hole, 1);
+                self.occupied.set(slot, 0);
+                hole = slot;
+            }
+        }
+        Some(value)
+    }
+
+    /// Removes all entries, keeps the allocated table.
+    pub fn clear(&mut self) {
+        self.occupied.as_mut_slice().fill(0);
+        self.len = 0;
+    }
+
+    /// Iterates over `(key, value)` pairs in an unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (0..self.slots())
+            .filter(|&slot| is_nonzero(unsafe { self.occupied.get_unchecked(slot) }))
+            .map(|slot| (unsafe { self.keys.get_unchecked(slot) }, unsafe { self.values.get_unchecked(slot) }))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = i64> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = i64> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    fn slots(&self) -> usize {
+        self.occupied.len()
+    }
+
+    fn home_slot(&self, key: i64) -> usize {
+        euclid_mod(key, self.slots() as i64) as usize
+    }
+
+    fn find(&self, key: i64) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        let slots = self.slots();
+        let mut slot = self.home_slot(key);
+        // The table is never full, so there always is an empty slot
+        while is_nonzero(unsafe { self.occupied.get_unchecked(slot) }) {
+            if eq(unsafe { self.keys.get_unchecked(slot) }, key) {
+                return Some(slot);
+            }
+            slot = next_slot(slot, slots);
+        }
+        None
+    }
+
+    /// Finds the slot of the key, or the empty slot where it should be inserted, growing first if needed.
+    fn find_slot_for_insert(&mut self, key: i64) -> usize {
+        if self.len + 1 > self.capacity() {
+            self.grow();
+        }
+        let slots = self.slots();
+        let mut slot = self.home_slot(key);
+        while is_nonzero(unsafe { self.occupied.get_unchecked(slot) }) && !eq(unsafe { self.keys.get_unchecked(slot) }, key) {
+            slot = next_slot(slot, slots);
+        }
+        slot
+    }
+
+    fn occupy(&mut self, slot: usize, key: i64) {
+        self.keys.set(slot, key);
+        self.occupied.set(slot, 1);
+        self.len += 1;
+    }
+
+    fn grow(&mut self) {
+        let size_index = if self.slots() == 0 { 0 } else { self.size_index + 1 };
+        let old = std::mem::take(self);
+        self.allocate(size_index);
+        for (key, value) in old.iter() {
+            let slot = self.find_slot_for_insert(key);
+            self.occupy(slot, key);
+            self.values.set(slot, value);
+        }
+    }
+
+    fn allocate(&mut self, size_index: usize) {
+        assert!(size_index < SIZES.len(), "RawHashMap is too large");
+        let slots = SIZES[size_index];
+        self.keys = std::iter::repeat_n(0, slots).collect();
+        self.values = std::iter::repeat_n(0, slots).collect();
+        self.occupied = std::iter::repeat_n(0, slots).collect();
+        self.size_index = size_index;
+    }
+}
+
+fn next_slot(slot: usize, slots: usize) -> usize {
+    if slot + 1 == slots { 0 } else { slot + 1 }
+}
+
+/// Maximum number of entries in a table with this many slots.
+fn max_len(slots: usize) -> usize {
+    slots / 4 * 3
+}
+
+fn size_index_for(capacity: usize) -> usize {
+    SIZES.iter().position(|&slots| max_len(slots) >= capacity).unwrap_or(SIZES.len())
+}
+
+impl std::fmt::Debug for RawHashMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A set of i64 values, the raw memory equivalent of `HashSet<i64>`, see [RawHashMap].
+#[derive(Clone, Default)]
+pub struct RawHashSet {
+    map: RawHashMap,
+}
+
+impl RawHashSet {
+    /// Creates an empty set, does not allocate.
+    pub fn new() -> Self {
+        RawHashSet { map: RawHashMap::new() }
+    }
+
+    /// Creates a set which can hold at least `capacity` values without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        RawHashSet { map: RawHashMap::with_capacity(capacity) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Adds the value, returns true if it was not in the set yet.
+    pub fn insert(&mut self, value: i64) -> bool {
+        self.map.insert(value, 0).is_none()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.map.contains_key(value)
+    }
+
+    /// Removes the value, returns true if it was in the set.
+    pub fn remove(&mut self, value: i64) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Iterates over the values in an unspecified order.
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        self.map.keys()
+    }
+}
+
+impl std::fmt::Debug for RawHashSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<i64> for RawHashSet {
+    fn from_iter<T: IntoIterator<Item = i64>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn sizes_are_primes() {
+        for size in SIZES {
+            assert!(crate::math::is_prime(size as i64), "{size}");
+        }
+    }
+
+    #[test]
+    fn map_matches_std() {
+        let mut map = RawHashMap::new();
+        let mut expected = HashMap::new();
+        // Structured keys, including ones that collide and extremes
+        let keys: Vec<i64> = (0..2000).map(|i| (i % 300) * 7 * 331 - 500 * (i % 3)).chain([i64::MIN, i64::MAX, 0, -1]).collect();
+        for (i, &key) in keys.iter().enumerate() {
+            let i = i as i64;
+            match i % 5 {
+                0 | 1 => assert_eq!(map.insert(key, i), expected.insert(key, i)),
+                2 => assert_eq!(map.remove(key), expected.remove(&key)),
+                3 => assert_eq!(map.add(key, 3), *expected.entry(key).and_modify(|v| *v += 3).or_insert(3)),
+                _ => assert_eq!(map.get(key), expected.get(&key).copied()),
+            }
+            assert_eq!(map.len(), expected.len());
+        }
+        for &key in &keys {
+            assert_eq!(map.get(key), expected.get(&key).copied(), "key {key}");
+        }
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort();
+        let mut expected_entries: Vec<_> = expected.into_iter().collect();
+        expected_entries.sort();
+        assert_eq!(entries, expected_entries);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(keys[0]), None);
+    }
+
+    #[test]
+    fn capacity_and_sets() {
+        let map = RawHashMap::with_capacity(100);
+        assert!(map.capacity() >= 100);
+        assert_eq!(RawHashMap::new().capacity(), 0);
+
+        let mut set: RawHashSet = [5, -5, 5, 12, 19].into_iter().collect();
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(-5) && !set.contains(6));
+        assert!(!set.insert(12));
+        assert!(set.remove(5));
+        assert!(!set.remove(5));
+        let mut values: Vec<_> = set.iter().collect();
+        values.sort();
+        assert_eq!(values, vec![-5, 12, 19]);
+    }
+}
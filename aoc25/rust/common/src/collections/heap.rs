@@ -0,0 +1,214 @@
+use super::RawHashMap;
+use crate::instructions::lt;
+use crate::raw_vec::RawFastVec;
+
+/// A binary heap of `(priority, payload)` pairs, the raw memory equivalent of `BinaryHeap<(i64, i64)>`.
+///
+/// Both values of a pair are stored in consecutive raw cells, priorities are compared with the
+/// branch-free [lt]. The order of pairs with equal priorities is unspecified.
+///
+/// [decrease_key](RawHeap::decrease_key) works by lazy deletion: the pair is pushed again and the
+/// pairs of the payload with a worse priority are skipped by [pop](RawHeap::pop), which is what Dijkstra needs.
+#[derive(Clone, Debug)]
+pub struct RawHeap {
+    /// `[priority_0, payload_0, priority_1, payload_1, ...]`
+    data: RawFastVec,
+    min: bool,
+    /// The best priority of every payload passed to [RawHeap::decrease_key].
+    best: RawHashMap,
+}
+
+impl RawHeap {
+    /// Creates a heap which pops the lowest priority first.
+    pub fn new_min() -> Self {
+        RawHeap { data: RawFastVec::new(), min: true, best: RawHashMap::new() }
+    }
+
+    /// Creates a heap which pops the highest priority first.
+    pub fn new_max() -> Self {
+        RawHeap { data: RawFastVec::new(), min: false, best: RawHashMap::new() }
+    }
+
+    /// Returns the number of stored pairs, including outdated ones which will be skipped.
+    pub fn len(&self) -> usize {
+        self.data.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Adds a pair to the heap.
+    pub fn push(&mut self, priority: i64, payload: i64) {
+        self.data.push(priority);
+        self.data.push(payload);
+        self.sift_up(self.len() - 1);
+    }
+
+    /// Pushes the payload with the priority, unless it was already pushed by this function with a priority
+    /// at least as good (lower for min heaps, higher for max heaps). Returns true if it was pushed.
+    ///
+    /// Once a better priority is pushed, the older pairs of the payload are never returned by [pop](RawHeap::pop).
+    pub fn decrease_key(&mut self, priority: i64, payload: i64) -> bool {
+        if let Some(best) = self.best.get(payload)
+            && !self.is_before(priority, best)
+        {
+            return false;
+        }
+        self.best.insert(payload, priority);
+        self.push(priority, payload);
+        true
+    }
+
+    /// Returns the best priority the payload was given by [decrease_key](RawHeap::decrease_key).
+    pub fn best_priority(&self, payload: i64) -> Option<i64> {
+        self.best.get(payload)
+    }
+
+    /// Removes the pair with the lowest (min heap) or highest (max heap) priority, skipping outdated pairs.
+    pub fn pop(&mut self) -> Option<(i64, i64)> {
+        loop {
+            let top = self.pop_any()?;
+            if !self.is_outdated(top) {
+                return Some(top);
+            }
+        }
+    }
+
+    /// Returns the pair [pop](RawHeap::pop) would return, removing outdated pairs from the top.
+    pub fn peek(&mut self) -> Option<(i64, i64)> {
+        while let Some(top) = self.top() {
+            if !self.is_outdated(top) {
+                return Some(top);
+            }
+            self.pop_any();
+        }
+        None
+    }
+
+    /// Removes all pairs and forgets the priorities of [decrease_key](RawHeap::decrease_key).
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.best.clear();
+    }
+
+    fn top(&self) -> Option<(i64, i64)> {
+        if self.is_empty() { None } else { Some((unsafe { self.data.get_unchecked(0) }, unsafe { self.data.get_unchecked(1) })) }
+    }
+
+    fn is_outdated(&self, (priority, payload): (i64, i64)) -> bool {
+        self.best.get(payload).is_some_and(|best| self.is_before(best, priority))
+    }
+
+    fn pop_any(&mut self) -> Option<(i64, i64)> {
+        let top = self.top()?;
+        let payload = self.data.pop().unwrap();
+        let priority = self.data.pop().unwrap();
+        if !self.is_empty() {
+            self.data.set(0, priority);
+            self.data.set(1, payload);
+            self.sift_down(0);
+        }
+        Some(top)
+    }
+
+    /// Whether priority `a` goes out of the heap before `b`.
+    fn is_before(&self, a: i64, b: i64) -> bool {
+        if self.min { lt(a, b) } else { lt(b, a) }
+    }
+
+    fn priority(&self, index: usize) -> i64 {
+        unsafe { self.data.get_unchecked(2 * index) }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        let mut data = self.data.as_mut_slice();
+        data.swap(2 * i, 2 * j);
+        data.swap(2 * i + 1, 2 * j + 1);
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.is_before(self.priority(index), self.priority(parent)) {
+                break;
+            }
+            self.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        let len = self.len();
+        loop {
+            let left = 2 * index + 1;
+            if left >= len {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < len && self.is_before(self.priority(right), self.priority(left)) { right } else { left };
+            if !self.is_before(self.priority(child), self.priority(index)) {
+                break;
+            }
+            self.swap(index, child);
+            index = child;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_order() {
+        let priorities = [5, -3, i64::MAX, 0, i64::MIN, 17, 5, 42, -100, 8];
+        let mut min = RawHeap::new_min();
+        let mut max = RawHeap::new_max();
+        for (payload, &priority) in priorities.iter().enumerate() {
+            min.push(priority, payload as i64);
+            max.push(priority, payload as i64);
+        }
+        assert_eq!(min.len(), priorities.len());
+        assert_eq!(min.peek(), Some((i64::MIN, 4)));
+
+        let mut sorted = priorities.to_vec();
+        sorted.sort();
+        let popped: Vec<i64> = std::iter::from_fn(|| min.pop()).map(|(priority, payload)| {
+            assert_eq!(priorities[payload as usize], priority);
+            priority
+        }).collect();
+        assert_eq!(popped, sorted);
+
+        sorted.reverse();
+        let popped: Vec<i64> = std::iter::from_fn(|| max.pop()).map(|(priority, _)| priority).collect();
+        assert_eq!(popped, sorted);
+        assert!(max.is_empty());
+    }
+
+    #[test]
+    fn decrease_key_skips_outdated_pairs() {
+        let mut heap = RawHeap::new_min();
+        assert!(heap.decrease_key(10, 1));
+        assert!(heap.decrease_key(5, 2));
+        assert!(heap.decrease_key(3, 1));
+        assert!(!heap.decrease_key(7, 1));
+        assert!(!heap.decrease_key(3, 1));
+        assert_eq!(heap.best_priority(1), Some(3));
+        assert_eq!(heap.len(), 3);
+        assert_eq!(heap.pop(), Some((3, 1)));
+        assert_eq!(heap.pop(), Some((5, 2)));
+        assert_eq!(heap.peek(), None);
+        assert_eq!(heap.pop(), None);
+
+        let mut heap = RawHeap::new_max();
+        heap.decrease_key(10, 1);
+        heap.decrease_key(20, 1);
+        heap.push(15, 2);
+        heap.push(30, 1);
+        assert_eq!(heap.pop(), Some((30, 1)));
+        assert_eq!(heap.pop(), Some((20, 1)));
+        assert_eq!(heap.pop(), Some((15, 2)));
+        assert_eq!(heap.pop(), None);
+    }
+}
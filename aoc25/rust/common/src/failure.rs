@@ -0,0 +1,108 @@
+//! Distinguishable failures.
+//!
+//! With `panic=immediate-abort`, every panic is the same `unreachable` trap, so a crashed run does not tell
+//! what went wrong. [fail] reports a code through the `env.fail` import first. wasm2ksplang translates it
+//! to a deliberate crash (division by zero) with the stack ending in `[code, FAIL_MARKER]`.
+//!
+//! Solutions should use non-negative codes, negative codes are used by `common` itself.
+
+use crate::trace::tag;
+
+/// The top of the stack after a failure, the code is right below it. These are the bytes of "FAIL".
+pub const FAIL_MARKER: i64 = tag("FAIL");
+
+/// An input value is not a valid character.
+pub const INVALID_INPUT_CHAR: i64 = -1;
+/// A [RawU64](crate::raw_i64::RawU64) operation went below zero.
+pub const UNSIGNED_UNDERFLOW: i64 = -2;
+/// A value does not fit into the [RawU64](crate::raw_i64::RawU64) domain (above i64::MAX).
+pub const UNSIGNED_OVERFLOW: i64 = -3;
+/// A [math](crate::math) function got an argument outside of its domain.
+pub const INVALID_ARGUMENT: i64 = -4;
+/// Something the solution expects is not in the input, e.g. a start position.
+pub const MISSING_INPUT: i64 = -5;
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+unsafe extern "C" {
+    /// Crashes the program with the given code on the stack, does not return.
+    #[link_name = "fail"]
+    fn _fail(code: i64);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::native::env::_fail;
+
+/// Crashes the program, leaving the code on the stack. Natively, this panics with the code.
+pub fn fail(code: i64) -> ! {
+    unsafe { _fail(code) };
+    // The host never returns from fail
+    std::process::abort()
+}
+
+/// Fails with the given code if the condition does not hold, see [fail].
+///
+/// ```should_panic
+/// common::ensure!(1 + 1 == 3, 42);
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $code:expr $(,)?) => {
+        if !$cond {
+            $crate::failure::fail($code)
+        }
+    };
+}
+
+/// Unwrapping which fails with a code instead of panicking, see [fail].
+pub trait OrFail<T> {
+    fn or_fail(self, code: i64) -> T;
+}
+
+impl<T> OrFail<T> for Option<T> {
+    fn or_fail(self, code: i64) -> T {
+        match self {
+            Some(value) => value,
+            None => fail(code),
+        }
+    }
+}
+
+impl<T, E> OrFail<T> for Result<T, E> {
+    fn or_fail(self, code: i64) -> T {
+        match self {
+            Ok(value) => value,
+            Err(_) => fail(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_spells_fail() {
+        assert_eq!(FAIL_MARKER, 0x4C494146);
+    }
+
+    #[test]
+    fn successful_checks() {
+        crate::ensure!(FAIL_MARKER > 0, 1);
+        assert_eq!(Some(5).or_fail(2), 5);
+        assert_eq!(Ok::<_, ()>(6).or_fail(3), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code 7")]
+    fn none_fails() {
+        None::<i64>.or_fail(7);
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -1")]
+    fn invalid_input_char() {
+        crate::native::set_input_values(&[i64::MAX]);
+        crate::read_input_to_string();
+    }
+}
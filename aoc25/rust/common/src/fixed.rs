@@ -0,0 +1,507 @@
+//! Fixed-point decimals as a substitute for floats, which wasm2ksplang cannot translate.
+//!
+//! A [`Fixed<SCALE>`](Fixed) is a [RawI64] holding the value multiplied by `10^SCALE`, i.e. with `SCALE`
+//! decimal places. Like [RawI64], operations crash the program on overflow and have `checked_*` variants.
+//! Multiplication and division never overflow in between, they only crash if the result does not fit.
+//! Results which are not exact are rounded according to a [Rounding] mode.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops;
+use crate::failure;
+use crate::instructions::{self, add_unchecked, cmp, div_unchecked, is_nonzero, is_zero, le, mul_unchecked, negate_unchecked, rem, sgn};
+use crate::raw_i64::RawI64;
+
+/// How inexact results are rounded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceil,
+    /// Drops the extra digits, like integer division. Used by the operators.
+    TowardZero,
+    /// To the nearest value, ties away from zero (2.5 -> 3, -2.5 -> -3).
+    HalfAwayFromZero,
+    /// To the nearest value, ties to the even one (2.5 -> 2, 3.5 -> 4).
+    HalfEven,
+}
+
+/// A decimal number with `SCALE` decimal places (at most 9), see the [module docs](self).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Fixed<const SCALE: u32>(RawI64);
+
+impl<const SCALE: u32> Fixed<SCALE> {
+    /// The raw value of 1, `10^SCALE`.
+    pub const ONE: i64 = {
+        // Products of two values below ONE have to fit into i64
+        assert!(SCALE <= 9, "Fixed supports at most 9 decimal places");
+        10i64.pow(SCALE)
+    };
+
+    pub const ZERO: Self = Fixed(RawI64::new(0));
+
+    /// Creates a value from its raw representation, the value multiplied by `10^SCALE`.
+    pub const fn from_raw(raw: RawI64) -> Self {
+        Fixed(raw)
+    }
+
+    /// Returns the raw representation, the value multiplied by `10^SCALE`.
+    pub fn to_raw(self) -> RawI64 {
+        self.0
+    }
+
+    /// Creates a value from an integer.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the result overflows.
+    pub fn from_int(value: i64) -> Self {
+        Fixed(RawI64::new(mul_unchecked(value, Self::ONE)))
+    }
+
+    /// Creates a value from an integer, returns None on overflow.
+    pub fn checked_from_int(value: i64) -> Option<Self> {
+        instructions::checked_mul(value, Self::ONE).map(Fixed)
+    }
+
+    /// Rounds the value to an integer, this is safe for all inputs.
+    pub fn to_int(self, rounding: Rounding) -> i64 {
+        round_div::<Crashing>(self.0.to_i64(), Self::ONE, rounding).unwrap()
+    }
+
+    /// Rounds the value to an integer, keeping it as a fixed point value.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the result overflows.
+    pub fn round(self, rounding: Rounding) -> Self {
+        Self::from_int(self.to_int(rounding))
+    }
+
+    /// `|self|`, crashes if the raw value is i64::MIN.
+    pub fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    /// Returns -1, 0 or 1 depending on the sign.
+    pub fn signum(self) -> i32 {
+        self.0.sgn()
+    }
+
+    /// `self * other`, rounded.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the result overflows.
+    pub fn mul_round(self, other: Self, rounding: Rounding) -> Self {
+        Fixed(mul_raw::<Crashing>(self.0.to_i64(), other.0.to_i64(), Self::ONE, rounding).unwrap().into())
+    }
+
+    /// `self / other`, rounded.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if other is zero or the result overflows.
+    pub fn div_round(self, other: Self, rounding: Rounding) -> Self {
+        Fixed(div_raw::<Crashing>(self.0.to_i64(), other.0.to_i64(), SCALE, rounding).unwrap().into())
+    }
+
+    /// `self + other`, returns None instead of crashing on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Fixed)
+    }
+
+    /// `self - other`, returns None instead of crashing on overflow.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Fixed)
+    }
+
+    /// `self * other` rounded, returns None instead of crashing on overflow.
+    pub fn checked_mul(self, other: Self, rounding: Rounding) -> Option<Self> {
+        mul_raw::<Checked>(self.0.to_i64(), other.0.to_i64(), Self::ONE, rounding).map(|raw| Fixed(raw.into()))
+    }
+
+    /// `self / other` rounded, returns None instead of crashing on overflow or division by zero.
+    pub fn checked_div(self, other: Self, rounding: Rounding) -> Option<Self> {
+        if is_zero(other.0.to_i64()) {
+            return None;
+        }
+        div_raw::<Checked>(self.0.to_i64(), other.0.to_i64(), SCALE, rounding).map(|raw| Fixed(raw.into()))
+    }
+
+    /// `self * factor`, for integer factors this is exact.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the result overflows.
+    pub fn mul_int(self, factor: i64) -> Self {
+        Fixed(RawI64::new(mul_unchecked(self.0.to_i64(), factor)))
+    }
+
+    /// `self / divisor`, rounded. Useful for averages.
+    ///
+    /// # Safety
+    /// This function leads to a program crash if the divisor is zero.
+    pub fn div_int(self, divisor: i64, rounding: Rounding) -> Self {
+        Fixed(round_div::<Crashing>(self.0.to_i64(), divisor, rounding).unwrap().into())
+    }
+
+    /// Square root, rounded down.
+    ///
+    /// This is Newton's method on the raw values, with the fixed point division, so it is exact for all values.
+    ///
+    /// # Safety
+    /// Fails with [INVALID_ARGUMENT](crate::failure::INVALID_ARGUMENT) if the value is negative.
+    pub fn sqrt(self) -> Self {
+        match self.signum() {
+            -1 => failure::fail(failure::INVALID_ARGUMENT),
+            0 => return self,
+            _ => {}
+        }
+        // isqrt(int part) + 1 is above the root
+        let mut x = Self::from_int(add_unchecked(crate::math::isqrt(self.to_int(Rounding::Floor)), 1));
+        loop {
+            let next = (x + self.div_round(x, Rounding::Floor)).div_int(2, Rounding::Floor);
+            if next >= x {
+                return x;
+            }
+            x = next;
+        }
+    }
+
+    /// Parses a decimal number like `-12.5`, returns None if it is invalid, has more than `SCALE`
+    /// decimal places or overflows.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty() || frac_part.len() > SCALE as usize || (digits.contains('.') && frac_part.is_empty()) {
+            return None;
+        }
+        // Accumulating a negative value, so the minimum fits
+        let mut raw: i64 = 0;
+        let padding = std::iter::repeat_n(&b'0', SCALE as usize - frac_part.len());
+        for &c in int_part.as_bytes().iter().chain(frac_part.as_bytes()).chain(padding) {
+            if !c.is_ascii_digit() {
+                return None;
+            }
+            raw = instructions::checked_mul(raw, 10)?.to_i64();
+            raw = instructions::checked_sub(raw, (c - b'0') as i64)?.to_i64();
+        }
+        let raw = if negative { raw } else { instructions::checked_neg(raw)?.to_i64() };
+        Some(Fixed(raw.into()))
+    }
+}
+
+impl<const SCALE: u32> ops::Add for Fixed<SCALE> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Fixed(self.0 + other.0)
+    }
+}
+
+impl<const SCALE: u32> ops::Sub for Fixed<SCALE> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Fixed(self.0 - other.0)
+    }
+}
+
+/// Rounds toward zero, see [Fixed::mul_round].
+impl<const SCALE: u32> ops::Mul for Fixed<SCALE> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.mul_round(other, Rounding::TowardZero)
+    }
+}
+
+/// Rounds toward zero, see [Fixed::div_round].
+impl<const SCALE: u32> ops::Div for Fixed<SCALE> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.div_round(other, Rounding::TowardZero)
+    }
+}
+
+impl<const SCALE: u32> ops::Neg for Fixed<SCALE> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Fixed(-self.0)
+    }
+}
+
+impl<const SCALE: u32> ops::AddAssign for Fixed<SCALE> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const SCALE: u32> ops::SubAssign for Fixed<SCALE> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const SCALE: u32> ops::MulAssign for Fixed<SCALE> {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl<const SCALE: u32> ops::DivAssign for Fixed<SCALE> {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+/// Formats the value with exactly `SCALE` decimal places.
+impl<const SCALE: u32> fmt::Display for Fixed<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let raw = self.0.to_i64();
+        let sign = if sgn(raw) == -1 { "-" } else { "" };
+        let int_part = div_unchecked(raw, Self::ONE).unsigned_abs();
+        if SCALE == 0 {
+            return write!(f, "{sign}{int_part}");
+        }
+        let frac_part = rem(raw, Self::ONE).unsigned_abs();
+        write!(f, "{sign}{int_part}.{frac_part:0width$}", width = SCALE as usize)
+    }
+}
+
+impl<const SCALE: u32> fmt::Debug for Fixed<SCALE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({self})")
+    }
+}
+
+/// The arithmetic used by the shared implementations, either crashing or checked.
+trait Arithmetic {
+    fn add(a: i64, b: i64) -> Option<i64>;
+    fn mul(a: i64, b: i64) -> Option<i64>;
+    fn div(a: i64, b: i64) -> Option<i64>;
+}
+
+struct Crashing;
+
+impl Arithmetic for Crashing {
+    fn add(a: i64, b: i64) -> Option<i64> {
+        Some(add_unchecked(a, b))
+    }
+
+    fn mul(a: i64, b: i64) -> Option<i64> {
+        Some(mul_unchecked(a, b))
+    }
+
+    fn div(a: i64, b: i64) -> Option<i64> {
+        Some(div_unchecked(a, b))
+    }
+}
+
+struct Checked;
+
+impl Arithmetic for Checked {
+    fn add(a: i64, b: i64) -> Option<i64> {
+        instructions::checked_add(a, b).map(|x| x.to_i64())
+    }
+
+    fn mul(a: i64, b: i64) -> Option<i64> {
+        instructions::checked_mul(a, b).map(|x| x.to_i64())
+    }
+
+    fn div(a: i64, b: i64) -> Option<i64> {
+        instructions::checked_div(a, b).map(|x| x.to_i64())
+    }
+}
+
+/// `a * b / one`, rounded.
+///
+/// With `a = a_hi * one + a_lo` and `b = b_hi * one + b_lo`, this is `a_hi * b + a_lo * b_hi + a_lo * b_lo / one`.
+/// The parts all have the sign of the result and `a_lo * b_lo < one^2 <= 10^18`, so nothing overflows in between.
+fn mul_raw<A: Arithmetic>(a: i64, b: i64, one: i64, rounding: Rounding) -> Option<i64> {
+    let (a_hi, a_lo) = (div_unchecked(a, one), rem(a, one));
+    let (b_hi, b_lo) = (div_unchecked(b, one), rem(b, one));
+    let low = mul_unchecked(a_lo, b_lo);
+    let whole = A::add(A::add(A::mul(a_hi, b)?, mul_unchecked(a_lo, b_hi))?, div_unchecked(low, one))?;
+    round_remainder::<A>(whole, rem(low, one), one, rounding)
+}
+
+/// `a * 10^scale / b`, rounded. The decimal places are long divided one by one, see [next_digit].
+fn div_raw<A: Arithmetic>(a: i64, b: i64, scale: u32, rounding: Rounding) -> Option<i64> {
+    let mut result = A::div(a, b)?;
+    let mut remainder = rem(a, b);
+    for _ in 0..scale {
+        let (digit, next_remainder) = next_digit(remainder, b);
+        result = A::add(A::mul(result, 10)?, digit)?;
+        remainder = next_remainder;
+    }
+    round_remainder::<A>(result, remainder, b, rounding)
+}
+
+/// `(10 * remainder / b, 10 * remainder % b)` for `|remainder| < |b|`, without overflowing in between.
+fn next_digit(remainder: i64, b: i64) -> (i64, i64) {
+    if let Some(shifted) = instructions::checked_mul(remainder, 10) {
+        let shifted = shifted.to_i64();
+        return (div_unchecked(shifted, b), rem(shifted, b));
+    }
+    // 10 * |remainder| does not fit (so |b| is huge), add |remainder| ten times modulo |b| instead.
+    // Everything is a negative absolute value, as |b| may be 2^63.
+    let neg_remainder = neg_abs(remainder);
+    // -(|b| - |remainder|), adding |remainder| to x reaches |b| if -x <= this
+    let threshold = add_unchecked(neg_abs(b), negate_unchecked(neg_remainder));
+    let mut neg_x = 0;
+    let mut digit = 0;
+    for _ in 0..10 {
+        if le(neg_x, threshold) {
+            neg_x = add_unchecked(neg_x, negate_unchecked(threshold));
+            digit = add_unchecked(digit, 1);
+        } else {
+            neg_x = add_unchecked(neg_x, neg_remainder);
+        }
+    }
+    // The quotient has the sign of remainder * b, the remainder keeps its sign
+    let digit = if sgn(remainder) == sgn(b) { digit } else { negate_unchecked(digit) };
+    let remainder = if sgn(remainder) == 1 { negate_unchecked(neg_x) } else { neg_x };
+    (digit, remainder)
+}
+
+/// `-|x|`, which never overflows.
+fn neg_abs(x: i64) -> i64 {
+    if sgn(x) == 1 { negate_unchecked(x) } else { x }
+}
+
+/// `a / b`, rounded.
+fn round_div<A: Arithmetic>(a: i64, b: i64, rounding: Rounding) -> Option<i64> {
+    round_remainder::<A>(A::div(a, b)?, rem(a, b), b, rounding)
+}
+
+/// Rounds a quotient truncated toward zero, given the remainder (with the sign of the dividend) and the divisor.
+fn round_remainder<A: Arithmetic>(quotient: i64, remainder: i64, divisor: i64, rounding: Rounding) -> Option<i64> {
+    if is_zero(remainder) {
+        return Some(quotient);
+    }
+    // The exact quotient is negative if the remainder and divisor have different signs
+    let direction = if sgn(remainder) == sgn(divisor) { 1 } else { -1 };
+    // Comparing |remainder| with |divisor| - |remainder| using negative absolute values, those never overflow
+    let neg_remainder = neg_abs(remainder);
+    let half = cmp(neg_remainder, add_unchecked(neg_abs(divisor), negate_unchecked(neg_remainder)));
+    let away = match rounding {
+        Rounding::Floor => direction == -1,
+        Rounding::Ceil => direction == 1,
+        Rounding::TowardZero => false,
+        Rounding::HalfAwayFromZero => half != 1,
+        Rounding::HalfEven => match half.cmp(&0) {
+            Ordering::Less => true,
+            Ordering::Equal => is_nonzero(rem(quotient, 2)),
+            Ordering::Greater => false,
+        },
+    };
+    if away { A::add(quotient, direction) } else { Some(quotient) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type F3 = Fixed<3>;
+
+    fn f3(text: &str) -> F3 {
+        F3::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(f3("12.5").to_raw().to_i64(), 12_500);
+        assert_eq!(f3("-0.001").to_string(), "-0.001");
+        assert_eq!(f3("7").to_string(), "7.000");
+        assert_eq!(F3::from_raw(RawI64::new(i64::MIN)).to_string(), "-9223372036854775.808");
+        assert_eq!(f3("-9223372036854775.808").to_raw().to_i64(), i64::MIN);
+        for invalid in ["", "-", "1.2345", "1.", ".5", "1a", "9223372036854775.808", "--1"] {
+            assert_eq!(F3::parse(invalid), None, "{invalid}");
+        }
+        assert_eq!(Fixed::<0>::parse("42").unwrap().to_string(), "42");
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(f3("1.5") + f3("2.25"), f3("3.75"));
+        assert_eq!(f3("1.5") - f3("2.25"), f3("-0.75"));
+        assert_eq!(f3("1.5") * f3("-2.25"), f3("-3.375"));
+        assert_eq!(f3("10") / f3("3"), f3("3.333"));
+        assert_eq!(f3("-2").div_round(f3("3"), Rounding::HalfAwayFromZero), f3("-0.667"));
+        assert_eq!(f3("0.001") * f3("0.5"), f3("0"));
+        assert_eq!(f3("0.001").mul_round(f3("0.5"), Rounding::HalfEven), f3("0"));
+        assert_eq!(f3("0.003").mul_round(f3("0.5"), Rounding::HalfEven), f3("0.002"));
+        assert_eq!(f3("7.5").div_int(2, Rounding::Ceil), f3("3.75"));
+        assert_eq!(f3("1").div_int(3, Rounding::Ceil), f3("0.334"));
+        assert_eq!(f3("2").mul_int(-4), f3("-8"));
+
+        // Large values do not overflow in between
+        let big = F3::from_int(3_000_000_000);
+        assert_eq!(big * f3("1000"), F3::from_int(3_000_000_000_000));
+        assert_eq!((big * f3("1000")) / big, f3("1000"));
+        assert_eq!(big.checked_mul(big, Rounding::Floor), None);
+        assert_eq!(f3("1").checked_div(f3("0"), Rounding::Floor), None);
+        assert_eq!(F3::from_raw(RawI64::new(i64::MAX)).checked_add(f3("0.001")), None);
+        assert_eq!(F3::checked_from_int(i64::MAX / 1000 + 1), None);
+        assert_eq!(Fixed::<9>::parse("2.5").unwrap() * Fixed::<9>::parse("-3000000000").unwrap(), Fixed::<9>::from_int(-7_500_000_000));
+    }
+
+    #[test]
+    fn division_near_limits() {
+        let raw = |value: i64| F3::from_raw(RawI64::new(value));
+        assert_eq!(raw(i64::MIN).checked_div(raw(-1), Rounding::Floor), None);
+        assert_eq!(raw(i64::MIN).checked_div(f3("-1"), Rounding::Floor), None);
+        assert_eq!(raw(i64::MAX - 1).checked_div(raw(i64::MAX), Rounding::Floor), Some(f3("0.999")));
+        assert_eq!(raw(i64::MAX - 1).checked_div(raw(i64::MAX), Rounding::Ceil), Some(f3("1")));
+        assert_eq!(raw(i64::MAX - 1).div_round(raw(-i64::MAX), Rounding::TowardZero), f3("-0.999"));
+        assert_eq!(raw(i64::MIN + 1).div_round(raw(i64::MIN), Rounding::Floor), f3("0.999"));
+        assert_eq!(raw(-3).div_round(raw(i64::MIN), Rounding::Floor), f3("0"));
+        assert_eq!(raw(i64::MAX / 2).div_round(raw(i64::MAX), Rounding::HalfEven), f3("0.5"));
+        assert_eq!(raw(i64::MIN).div_int(1, Rounding::Floor), raw(i64::MIN));
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: integer overflow")]
+    fn division_overflow_crashes() {
+        F3::from_raw(RawI64::new(i64::MIN)).div_int(-1, Rounding::Floor);
+    }
+
+    #[test]
+    fn rounding() {
+        let cases = [
+            ("2.5", [2, 3, 2, 3, 2]),
+            ("3.5", [3, 4, 3, 4, 4]),
+            ("-2.5", [-3, -2, -2, -3, -2]),
+            ("-2.4", [-3, -2, -2, -2, -2]),
+            ("2.6", [2, 3, 2, 3, 3]),
+            ("-7", [-7, -7, -7, -7, -7]),
+        ];
+        let modes = [Rounding::Floor, Rounding::Ceil, Rounding::TowardZero, Rounding::HalfAwayFromZero, Rounding::HalfEven];
+        for (text, expected) in cases {
+            for (mode, expected) in modes.into_iter().zip(expected) {
+                assert_eq!(f3(text).to_int(mode), expected, "{text} {mode:?}");
+            }
+        }
+        assert_eq!(f3("-2.5").round(Rounding::Floor), f3("-3"));
+    }
+
+    #[test]
+    fn square_roots() {
+        assert_eq!(f3("2").sqrt(), f3("1.414"));
+        assert_eq!(f3("0.25").sqrt(), f3("0.5"));
+        assert_eq!(f3("0").sqrt(), f3("0"));
+        assert_eq!(F3::from_int(1 << 50).sqrt(), F3::from_int(1 << 25));
+        assert_eq!(F3::from_raw(RawI64::new(i64::MAX)).sqrt(), f3("96038388.349"));
+        assert_eq!(Fixed::<9>::parse("9000000000").unwrap().sqrt().to_string(), "94868.329805051");
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -4")]
+    fn negative_square_root_fails() {
+        f3("-1").sqrt();
+    }
+}
@@ -0,0 +1,195 @@
+use crate::input_utils::find_first_char;
+use crate::raw_i64::RawI64;
+use crate::raw_input::is_char;
+use crate::{input_size, read_input_raw, set_input};
+
+/// Offsets of the 4 orthogonal neighbours.
+pub const NEIGHBORS_4: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Offsets of all 8 neighbours, including diagonal ones.
+pub const NEIGHBORS_8: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// A character grid stored directly in the input, rows separated by line breaks.
+///
+/// Values can be overwritten in place with [set](InputGrid::set), which is a lot cheaper
+/// than copying the grid into WASM memory. Coordinates are `(x, y)`, `(0, 0)` is the top left corner.
+#[derive(Copy, Clone, Debug)]
+pub struct InputGrid {
+    width: u32,
+    height: u32,
+}
+
+impl InputGrid {
+    /// Detects the size of the grid in the input, the final line break is optional.
+    pub fn new() -> Self {
+        let input_size = input_size();
+        let width = find_first_char(0, input_size, '\n');
+        // +1 for the line break, which might be missing at the end
+        let height = (input_size + 1) / (width + 1);
+        InputGrid { width, height }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the input index of the cell.
+    pub fn index(&self, x: u32, y: u32) -> u32 {
+        y * (self.width + 1) + x // +1 for line break
+    }
+
+    /// Returns the coordinates of the cell at the given input index.
+    pub fn position(&self, index: u32) -> (u32, u32) {
+        (index % (self.width + 1), index / (self.width + 1))
+    }
+
+    /// Checks whether the coordinates are inside the grid.
+    ///
+    /// Coordinates which went below zero wrap around to huge u32 values, so they are out of bounds too.
+    pub fn in_bounds(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Gets the value of the cell.
+    ///
+    /// SAFETY:
+    /// No bounds checks are performed, out of bounds coordinates read other cells, line breaks or crash.
+    pub fn get(&self, x: u32, y: u32) -> RawI64 {
+        read_input_raw(self.index(x, y))
+    }
+
+    /// Gets the value of the cell, or None if out of bounds.
+    pub fn get_checked(&self, x: u32, y: u32) -> Option<RawI64> {
+        if self.in_bounds(x, y) { Some(self.get(x, y)) } else { None }
+    }
+
+    /// Overwrites the value of the cell in the input.
+    ///
+    /// SAFETY:
+    /// No bounds checks are performed, see [get](InputGrid::get).
+    pub fn set(&self, x: u32, y: u32, value: i64) {
+        set_input(self.index(x, y), value)
+    }
+
+    /// Checks if the cell contains the character, see [is_char].
+    pub fn is_char(&self, x: u32, y: u32, c: char) -> bool {
+        is_char(self.get(x, y), c)
+    }
+
+    /// Iterates over the in-bounds orthogonal neighbours of the cell.
+    pub fn neighbors4(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> + use<> {
+        self.neighbors(x, y, NEIGHBORS_4)
+    }
+
+    /// Iterates over the in-bounds neighbours of the cell, including diagonal ones.
+    pub fn neighbors8(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> + use<> {
+        self.neighbors(x, y, NEIGHBORS_8)
+    }
+
+    fn neighbors<const N: usize>(&self, x: u32, y: u32, offsets: [(i32, i32); N]) -> impl Iterator<Item = (u32, u32)> + use<N> {
+        let grid = *self;
+        offsets.into_iter()
+            .map(move |(dx, dy)| (x.wrapping_add(dx as u32), y.wrapping_add(dy as u32)))
+            .filter(move |&(x, y)| grid.in_bounds(x, y))
+    }
+
+    /// Iterates over the values in a row.
+    pub fn row(&self, y: u32) -> impl Iterator<Item = RawI64> + use<> {
+        let start = self.index(0, y);
+        (start..start + self.width).map(read_input_raw)
+    }
+
+    /// Iterates over the values in a column.
+    pub fn column(&self, x: u32) -> impl Iterator<Item = RawI64> + use<> {
+        let grid = *self;
+        (0..self.height).map(move |y| grid.get(x, y))
+    }
+
+    /// Iterates over coordinates of all cells, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (u32, u32)> + use<> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    /// Finds the first cell containing the character, row by row.
+    pub fn find(&self, c: char) -> Option<(u32, u32)> {
+        let end = self.index(0, self.height).min(input_size());
+        let index = find_first_char(0, end, c);
+        if index < end { Some(self.position(index)) } else { None }
+    }
+
+    /// Rewrites the characters of the grid to numeric codes in place.
+    ///
+    /// Each cell containing one of the characters in `codes` is replaced by the corresponding number,
+    /// other cells (and line breaks) are left untouched. This is useful when the grid is used
+    /// to store numbers afterward, as comparing against small numbers is cheap.
+    pub fn rewrite(&self, codes: &[(char, i64)]) {
+        for (x, y) in self.positions() {
+            let value = self.get(x, y);
+            for &(c, code) in codes {
+                if is_char(value, c) {
+                    self.set(x, y, code);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Default for InputGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::set_input_str;
+
+    #[test]
+    fn grid_access() {
+        set_input_str("#.S\n..#\n#..\n.#.");
+        let grid = InputGrid::new();
+        assert_eq!((grid.width(), grid.height()), (3, 4));
+        assert!(grid.is_char(2, 0, 'S'));
+        assert_eq!(grid.find('S'), Some((2, 0)));
+        assert_eq!(grid.find('X'), None);
+        assert_eq!(grid.get_checked(3, 0), None);
+        assert_eq!(grid.get_checked(0, 3), Some(RawI64::new('.' as i64)));
+        assert_eq!(grid.position(grid.index(1, 2)), (1, 2));
+
+        assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8(2, 3).collect::<Vec<_>>(), vec![(1, 2), (2, 2), (1, 3)]);
+        assert_eq!(grid.neighbors8(1, 1).count(), 8);
+
+        let hashes = |cells: &mut dyn Iterator<Item = RawI64>| cells.filter(|&c| is_char(c, '#')).count();
+        assert_eq!(hashes(&mut grid.row(1)), 1);
+        assert_eq!(hashes(&mut grid.column(0)), 2);
+    }
+
+    #[test]
+    fn rewrite_to_numbers() {
+        set_input_str("S.^\n.^.\n");
+        let grid = InputGrid::new();
+        assert_eq!(grid.height(), 2);
+        grid.rewrite(&[('.', 0), ('^', -1), ('S', 1)]);
+        assert_eq!(grid.row(0).map(|c| c.to_i64()).collect::<Vec<_>>(), vec![1, 0, -1]);
+        grid.set(0, 1, 42);
+        assert_eq!(grid.get(0, 1).to_i64(), 42);
+        assert_eq!(crate::read_input(3), '\n' as i64);
+    }
+}
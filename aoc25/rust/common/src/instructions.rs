@@ -1,4 +1,16 @@
+use crate::raw_i64::RawI64;
+
 // ksplang instructions exported as functions, these should be super fast
+//
+// All modes of the u instruction have their own function (u_add, u_subabs, ...). Instructions which are
+// intentionally not exported:
+// - ++, pop, pop2, swap, l-swap, lroll: plain stack manipulation, the translator emits those itself
+// - brz, j, call, goto, rev, deez: control flow, which only the translator can manage
+// - sum, -ff, kpi: work with the whole stack, which contains the translator's locals and memory
+// - m, d (median and gcd of the top n values), praise: a variable number of stack arguments or results,
+//   covered by max, gcd and Output
+// - ^^: tetr with swapped arguments
+#[cfg(target_arch = "wasm32")]
 #[link(wasm_import_module = "ksplang")]
 unsafe extern "C" {
     #[link_name = "max"]
@@ -36,15 +48,47 @@ unsafe extern "C" {
     fn _funkcia(a: i64, b: i64) -> u32;
     #[link_name = "spanek"]
     fn _spanek();
+    // bulkxor with a fixed count of 1, so it always has exactly one result
+    #[link_name = "bulkxor"]
+    fn _bulkxor(a: i64, b: i64) -> i32;
+    // qeq has a variable number of stack results (0, 1 or 2 roots), so the host splits it into
+    // functions with a fixed number of results, all solving the same equation.
+    // qeq_count puts a 0 below the roots as a sentinel, so c must not be zero (0 would be a root).
+    // qeq_single may only be used with exactly one root, qeq_root with exactly two.
+    #[link_name = "qeq_count"]
+    fn _qeq_count(c: i64, b: i64, a: i64) -> i32;
+    #[link_name = "qeq_single"]
+    fn _qeq_single(c: i64, b: i64, a: i64) -> i64;
+    #[link_name = "qeq_root"]
+    fn _qeq_root(c: i64, b: i64, a: i64, index: i32) -> i64;
     // Most efficient negation is done through qeq, which has a variable number of stack results,
-    // it's a bit too risky to expose it directly.
+    // so it gets its own function.
     #[link_name = "negate"]
     fn _negate(a: i64) -> i64;
     // Division is also not native
     #[link_name = "div"]
     fn _div(a: i64, b: i64) -> i64;
+    // Branch-free comparisons, all of these return 0 or 1.
+    // LLVM may compile `x == 0` to `i64.const 0; i64.ne` instead of the cheap `i64.eqz`,
+    // these always use the cheap sequences.
+    #[link_name = "is_zero"]
+    fn _is_zero(a: i64) -> i32;
+    #[link_name = "is_nonzero"]
+    fn _is_nonzero(a: i64) -> i32;
+    #[link_name = "eq"]
+    fn _eq(a: i64, b: i64) -> i32;
+    #[link_name = "lt"]
+    fn _lt(a: i64, b: i64) -> i32;
+    #[link_name = "le"]
+    fn _le(a: i64, b: i64) -> i32;
+    // The condition is on the top of the stack, it is used as the distance of a roll of the two values
+    #[link_name = "select"]
+    fn _select(if_false: i64, if_true: i64, cond: i32) -> i64;
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::native::ksplang::*;
+
 /// Calls the ksplang funkcia instruction, this is safe for all inputs.
 /// Returns the result of funkcia(a, b).
 pub fn funkcia(a: i64, b: i64) -> u32 {
@@ -125,7 +169,7 @@ pub fn rem(dividend: i64, divisor: i64) -> i64 {
 /// This function leads to a program crash if b == 0.
 pub fn euclid_mod(dividend: i64, divisor: i64) -> i64 {
     // Note the swapped parameters
-    unsafe { _rem(divisor, dividend) }
+    unsafe { _mod(divisor, dividend) }
 }
 
 /// Calls the ksplang tetr instruction.
@@ -174,6 +218,50 @@ pub fn gcd_unchecked(a: i64, b: i64) -> i64 {
 }
 
 
+/// Calls the ksplang bulkxor instruction with a single pair, this is safe for all inputs.
+/// Result: `(a > 0) xor (b > 0)` as 0 or 1.
+pub fn bulkxor(a: i64, b: i64) -> i32 {
+    unsafe { _bulkxor(a, b) }
+}
+
+/// Calls the ksplang spanek instruction. This ends the program with a timeout.
+pub fn spanek() {
+    unsafe { _spanek() }
+}
+
+/// Integer roots of a quadratic equation, the result of the ksplang qeq instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QeqRoots {
+    None,
+    One(i64),
+    /// Two different roots, the smaller one is first.
+    Two(i64, i64),
+}
+
+/// Calls the ksplang qeq instruction.
+/// Result: integer roots of `a*x^2 + b*x + c = 0`, also works for linear equations (a = 0).
+///
+/// # Safety
+/// This function leads to a program crash if a == b == c == 0 (0 = 0 has infinitely many roots)
+/// or if a root does not fit into i64.
+pub fn qeq(a: i64, b: i64, c: i64) -> QeqRoots {
+    if is_zero(c) && !(is_zero(a) && is_zero(b)) {
+        // x * (a*x + b) = 0, so 0 is a root and the host cannot use it as a sentinel.
+        // The other root is the root of a*x + b = 0, which does not have 0 as a root (or is 0 = 0 for a == b == 0).
+        let other = if is_zero(a) { QeqRoots::None } else { qeq(0, a, b) };
+        return match other {
+            QeqRoots::One(x) if is_nonzero(x) => if lt(x, 0) { QeqRoots::Two(x, 0) } else { QeqRoots::Two(0, x) },
+            _ => QeqRoots::One(0),
+        };
+    }
+    // Note the reversed parameters, a is on the top of the stack
+    match unsafe { _qeq_count(c, b, a) } {
+        0 => QeqRoots::None,
+        1 => QeqRoots::One(unsafe { _qeq_single(c, b, a) }),
+        _ => QeqRoots::Two(unsafe { _qeq_root(c, b, a, 0) }, unsafe { _qeq_root(c, b, a, 1) }),
+    }
+}
+
 /// Negates a number, composite of multiple instructions.
 ///
 /// # Safety
@@ -189,4 +277,349 @@ pub fn negate_unchecked(a: i64) -> i64 {
 pub fn div_unchecked(a: i64, b: i64) -> i64 {
     // Note the swapped parameters
     unsafe { _div(b, a) }
-}
\ No newline at end of file
+}
+
+/// Bitwise not, composite of multiple instructions, this is safe for all inputs.
+/// Result: `!a`.
+pub fn bitnot(a: i64) -> i64 {
+    // !a = -a - 1, we just have to avoid overflows on the way
+    if sgn(a) == -1 {
+        negate_unchecked(add_unchecked(a, 1))
+    } else {
+        add_unchecked(negate_unchecked(a), -1)
+    }
+}
+
+/// Bitwise or, composite of multiple instructions, this is safe for all inputs.
+/// Result: `a | b`.
+pub fn bitor(a: i64, b: i64) -> i64 {
+    // The two parts have no bits in common, so the addition has no carries and cannot overflow
+    add_unchecked(a, and(b, bitnot(a)))
+}
+
+/// Bitwise xor, composite of multiple instructions, this is safe for all inputs.
+/// Result: `a ^ b`.
+pub fn bitxor(a: i64, b: i64) -> i64 {
+    // The two parts have no bits in common, so the addition has no carries and cannot overflow
+    add_unchecked(and(a, bitnot(b)), and(b, bitnot(a)))
+}
+
+/// Arithmetic right shift, composite of multiple instructions.
+/// Result: `num >> by`, shifting by 64 or more results in 0 or -1 (unlike Rust).
+///
+/// # Safety
+/// This crashes the program with negative `by` values.
+pub fn shift_right(num: i64, by: i64) -> i64 {
+    if subabs_unchecked(max(by, 62), 62) != 0 {
+        // 1 << 63 is negative, by > 62 only leaves the sign
+        return if sgn(num) == -1 { -1 } else { 0 };
+    }
+    let power = bitshift(1, by);
+    // Clearing the low bits first makes the division exact, so it rounds down like a shift should
+    let low_bits = and(num, add_unchecked(power, -1));
+    div_unchecked(add_unchecked(num, negate_unchecked(low_bits)), power)
+}
+
+/// Compares two numbers using sgn and max, this is safe for all inputs.
+/// Result: `sgn(a - b)`, without the overflow.
+pub fn cmp(a: i64, b: i64) -> i32 {
+    let sgn_a = sgn(a);
+    let sgn_b = sgn(b);
+    if sgn_a != sgn_b {
+        return if sgn_a > sgn_b { 1 } else { -1 };
+    }
+    // Same sign, the difference fits
+    if subabs_unchecked(a, b) == 0 {
+        0
+    } else if subabs_unchecked(max(a, b), a) == 0 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Checks whether the value is zero using sgn and bulkxor (zeroNot), this is safe for all inputs.
+/// Result: `a == 0`.
+pub fn is_zero(a: i64) -> bool {
+    unsafe { _is_zero(a) != 0 }
+}
+
+/// Checks whether the value is not zero using `|sgn(a)|`, this is safe for all inputs.
+/// Result: `a != 0`.
+pub fn is_nonzero(a: i64) -> bool {
+    unsafe { _is_nonzero(a) != 0 }
+}
+
+/// Checks two values for equality using [cmp] and zeroNot, this is safe for all inputs.
+/// Result: `a == b`.
+///
+/// If the difference of the values cannot overflow, `subabs_unchecked(a, b) == 0` is cheaper.
+pub fn eq(a: i64, b: i64) -> bool {
+    unsafe { _eq(a, b) != 0 }
+}
+
+/// Compares two values using [cmp], this is safe for all inputs.
+/// Result: `a < b`.
+pub fn lt(a: i64, b: i64) -> bool {
+    unsafe { _lt(a, b) != 0 }
+}
+
+/// Compares two values using [cmp], this is safe for all inputs.
+/// Result: `a <= b`.
+pub fn le(a: i64, b: i64) -> bool {
+    unsafe { _le(a, b) != 0 }
+}
+
+/// Selects one of two values without branching, this is safe for all inputs.
+/// Result: `if cond { a } else { b }`.
+///
+/// Both values are always evaluated, so this is only worth it for cheap ones.
+pub fn select(cond: bool, a: i64, b: i64) -> i64 {
+    unsafe { _select(b, a, cond as i32) }
+}
+
+// Checked variants of the instructions above.
+//
+// These never crash the program, they return None instead. All overflow checks are done
+// with cheap ksplang instructions (sgn, lensum, ...) and zero checks, we never use generic i64
+// comparisons or wrapping arithmetic as those are very expensive after translation.
+
+/// Returns true if the value is [i64::MIN], this is safe for all inputs.
+pub fn is_min(a: i64) -> bool {
+    // Only 0 and i64::MIN have no bits in common with i64::MAX
+    and(a, i64::MAX) == 0 && sgn(a) == -1
+}
+
+/// Adds two numbers using the ksplang u instruction, returns None on overflow.
+/// Result: `a + b`.
+pub fn checked_add(a: i64, b: i64) -> Option<RawI64> {
+    let sgn_a = sgn(a);
+    if sgn_a == 0 || sgn_a != sgn(b) {
+        // Cannot overflow with different signs
+        return Some(add_unchecked(a, b).into());
+    }
+
+    if sgn_a == 1 {
+        // a + b > MAX <=> (a - MAX) + b > 0, neither step can overflow for positive a, b
+        if sgn(add_unchecked(add_unchecked(a, -i64::MAX), b)) == 1 {
+            return None;
+        }
+    } else {
+        // a + b < MIN <=> (a + 1) + (b + MAX) < 0, neither step can overflow for negative a, b
+        if sgn(add_unchecked(add_unchecked(a, 1), add_unchecked(b, i64::MAX))) == -1 {
+            return None;
+        }
+    }
+    Some(add_unchecked(a, b).into())
+}
+
+/// Subtracts two numbers, returns None on overflow.
+/// Result: `a - b`.
+pub fn checked_sub(a: i64, b: i64) -> Option<RawI64> {
+    if is_min(b) {
+        // -MIN does not exist, but a - MIN fits if a is negative
+        return if sgn(a) == -1 {
+            Some(add_unchecked(add_unchecked(a, i64::MAX), 1).into())
+        } else {
+            None
+        };
+    }
+    checked_add(a, negate_unchecked(b))
+}
+
+/// Negates a number, returns None for [i64::MIN].
+/// Result: `-a`.
+pub fn checked_neg(a: i64) -> Option<RawI64> {
+    if is_min(a) {
+        None
+    } else {
+        Some(negate_unchecked(a).into())
+    }
+}
+
+/// Calculates the absolute value, returns None for [i64::MIN].
+/// Result: `|a|`.
+pub fn checked_abs(a: i64) -> Option<RawI64> {
+    if is_min(a) {
+        None
+    } else {
+        Some(subabs_unchecked(a, 0).into())
+    }
+}
+
+/// Calculates the absolute value of the difference, returns None on overflow.
+/// Result: `|a - b|`.
+pub fn checked_subabs(a: i64, b: i64) -> Option<RawI64> {
+    if sgn(a) == sgn(b) && sgn(a) != 0 {
+        // Same sign, the difference is always smaller
+        return Some(subabs_unchecked(a, b).into());
+    }
+    checked_sub(a, b).and_then(|diff| checked_abs(diff.to_i64()))
+}
+
+/// Multiplies two numbers using the ksplang u instruction, returns None on overflow.
+/// Result: `a * b`.
+pub fn checked_mul(a: i64, b: i64) -> Option<RawI64> {
+    let digits = lensum(a, b);
+    if digits <= 18 {
+        // |a * b| < 10^18, this includes zeroes
+        return Some(mul_unchecked(a, b).into());
+    }
+    if digits > 20 {
+        // |a * b| >= 10^19
+        return None;
+    }
+    if a == 0 || b == 0 {
+        return Some(0.into());
+    }
+
+    // We work with negative absolute values as those exist for all i64 values.
+    let neg_abs_a = if sgn(a) == 1 { negate_unchecked(a) } else { a };
+    let neg_abs_b = if sgn(b) == 1 { negate_unchecked(b) } else { b };
+    let limit = if sgn(a) == sgn(b) {
+        // |a| <= MAX / |b|
+        div_unchecked(i64::MAX, neg_abs_b)
+    } else if add_unchecked(neg_abs_b, 1) == 0 {
+        // |b| = 1, the result is -|a|
+        return Some(mul_unchecked(a, b).into());
+    } else {
+        // |a| <= 2^63 / |b|
+        negate_unchecked(div_unchecked(i64::MIN, neg_abs_b))
+    };
+
+    // -|a| >= limit, neither value is positive, so the difference cannot overflow
+    if sgn(add_unchecked(neg_abs_a, negate_unchecked(limit))) == -1 {
+        None
+    } else {
+        Some(mul_unchecked(a, b).into())
+    }
+}
+
+/// Divides two numbers, returns None if b == 0 or the result overflows (`i64::MIN / -1`).
+/// Result: `a / b` (rounded towards zero).
+pub fn checked_div(a: i64, b: i64) -> Option<RawI64> {
+    if b == 0 || (sgn(b) == -1 && add_unchecked(b, 1) == 0 && is_min(a)) {
+        None
+    } else {
+        Some(div_unchecked(a, b).into())
+    }
+}
+
+/// Calculates the remainder, returns None if b == 0.
+/// Result: `a rem b` (like C %).
+pub fn checked_rem(dividend: i64, divisor: i64) -> Option<RawI64> {
+    if divisor == 0 {
+        None
+    } else {
+        Some(rem(dividend, divisor).into())
+    }
+}
+
+/// Calculates the euclidean modulo, returns None if b == 0.
+/// Result: `a % b` (like Rust euclid_mod).
+pub fn checked_euclid_mod(dividend: i64, divisor: i64) -> Option<RawI64> {
+    if divisor == 0 {
+        None
+    } else {
+        Some(euclid_mod(dividend, divisor).into())
+    }
+}
+
+/// Calculates factorial, returns None on overflow.
+/// Result: `|a|!`.
+pub fn checked_factorial(a: i64) -> Option<RawI64> {
+    // 20! is the largest factorial which fits into i64
+    let abs = checked_abs(a)?.to_i64();
+    if subabs_unchecked(max(abs, 20), 20) == 0 {
+        Some(factorial_unchecked(abs).into())
+    } else {
+        None
+    }
+}
+
+/// Calculates the greatest common divisor, returns None if the result does not fit into i64.
+/// Result: `gcd(a, b)`.
+pub fn checked_gcd(a: i64, b: i64) -> Option<RawI64> {
+    // The result is only 2^63 if both are either 0 or i64::MIN
+    if (a == 0 || is_min(a)) && (b == 0 || is_min(b)) && !(a == 0 && b == 0) {
+        None
+    } else {
+        Some(gcd_unchecked(a, b).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDGE_VALUES: [i64; 21] = [
+        i64::MIN, i64::MIN + 1, i64::MIN / 2, -4_294_967_296, -3_037_000_500, -3_037_000_499, -1_000_000_007,
+        -10, -2, -1, 0, 1, 2, 10, 1_000_000_007, 3_037_000_499, 3_037_000_500, 4_294_967_296,
+        i64::MAX / 2, i64::MAX - 1, i64::MAX,
+    ];
+
+    fn raw(value: Option<i64>) -> Option<RawI64> {
+        value.map(RawI64::new)
+    }
+
+    #[test]
+    fn checked_ops_match_std() {
+        for a in EDGE_VALUES {
+            assert_eq!(checked_neg(a), raw(a.checked_neg()), "-{a}");
+            assert_eq!(checked_abs(a), raw(a.checked_abs()), "|{a}|");
+            assert_eq!(is_min(a), a == i64::MIN);
+            for b in EDGE_VALUES {
+                assert_eq!(checked_add(a, b), raw(a.checked_add(b)), "{a} + {b}");
+                assert_eq!(checked_sub(a, b), raw(a.checked_sub(b)), "{a} - {b}");
+                assert_eq!(checked_mul(a, b), raw(a.checked_mul(b)), "{a} * {b}");
+                assert_eq!(checked_div(a, b), raw(a.checked_div(b)), "{a} / {b}");
+                assert_eq!(checked_euclid_mod(a, b), raw((a as i128).checked_rem_euclid(b as i128).map(|x| x as i64)), "{a} % {b}");
+                let subabs = i64::try_from(a.abs_diff(b)).ok();
+                assert_eq!(checked_subabs(a, b), raw(subabs), "|{a} - {b}|");
+            }
+        }
+    }
+
+    #[test]
+    fn composite_ops_match_std() {
+        for a in EDGE_VALUES {
+            assert_eq!(bitnot(a), !a, "!{a}");
+            for by in [0, 1, 2, 31, 32, 62, 63, 64, 100] {
+                assert_eq!(shift_right(a, by), a >> by.min(63), "{a} >> {by}");
+            }
+            for b in EDGE_VALUES {
+                assert_eq!(bitor(a, b), a | b, "{a} | {b}");
+                assert_eq!(bitxor(a, b), a ^ b, "{a} ^ {b}");
+                assert_eq!(cmp(a, b), a.cmp(&b) as i32, "{a} cmp {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn checked_factorial_limits() {
+        assert_eq!(checked_factorial(-20), Some(2_432_902_008_176_640_000.into()));
+        assert_eq!(checked_factorial(21), None);
+        assert_eq!(checked_factorial(i64::MIN), None);
+    }
+
+    #[test]
+    fn euclid_mod_is_not_rem() {
+        assert_eq!(euclid_mod(-7, 3), 2);
+        assert_eq!(rem(-7, 3), -1);
+    }
+
+    #[test]
+    fn branch_free_comparisons() {
+        let values = [i64::MIN, i64::MIN + 1, -5, -1, 0, 1, 5, i64::MAX - 1, i64::MAX];
+        for a in values {
+            assert_eq!(is_zero(a), a == 0);
+            assert_eq!(is_nonzero(a), a != 0);
+            for b in values {
+                assert_eq!(eq(a, b), a == b, "{a} == {b}");
+                assert_eq!(lt(a, b), a < b, "{a} < {b}");
+                assert_eq!(le(a, b), a <= b, "{a} <= {b}");
+                assert_eq!(select(lt(a, b), a, b), a.min(b));
+            }
+        }
+    }
+}
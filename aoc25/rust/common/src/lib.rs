@@ -2,11 +2,33 @@ pub mod instructions;
 pub mod raw_input;
 pub mod raw_i64;
 pub mod input_utils;
+pub mod grid;
 pub mod raw_array;
+pub mod raw_vec;
+pub mod raw_arena;
+pub mod sort;
+pub mod collections;
+pub mod bitset;
+pub mod search;
+pub mod math;
+pub mod bigint;
+pub mod fixed;
+pub mod lookup;
+pub mod output;
+pub mod trace;
+pub mod failure;
+pub mod allocator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
+
+pub use common_macros::ksplang_solve;
+pub use failure::fail;
 
 use std::alloc::{Layout, alloc};
+use crate::failure::OrFail;
 use crate::raw_i64::RawI64;
 
+#[cfg(target_arch = "wasm32")]
 #[link(wasm_import_module = "env")]
 unsafe extern "C" {
     #[link_name = "input_size"]
@@ -24,6 +46,9 @@ unsafe extern "C" {
     fn _set_input(value: i64, index: u32);
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::native::env::*;
+
 pub fn input_size() -> u32 {
     unsafe { _input_size() }
 }
@@ -46,7 +71,7 @@ pub fn read_input_to_string() -> String {
     let mut input = String::new();
     for i in 0..size {
         let value = read_input(i);
-        let c = std::char::from_u32(value as u32).unwrap();
+        let c = u32::try_from(value).ok().and_then(char::from_u32).or_fail(failure::INVALID_INPUT_CHAR);
         input.push(c);
     }
     input
@@ -58,7 +83,7 @@ pub fn read_input_to_lines() -> Vec<String> {
     let mut current_line = String::new();
     for i in 0..size {
         let value = read_input(i);
-        let c = std::char::from_u32(value as u32).unwrap();
+        let c = u32::try_from(value).ok().and_then(char::from_u32).or_fail(failure::INVALID_INPUT_CHAR);
         if c == '\n' {
             lines.push(current_line);
             current_line = String::new();
@@ -0,0 +1,172 @@
+//! Constant lookup tables which live directly on the ksplang stack.
+//!
+//! Tables defined with [lookup_table!](crate::lookup_table) are emitted into the `ksplang_lookup` WASM custom section,
+//! so the host can place them on the stack instead of us building them in (byte-based) WASM memory at runtime.
+//! Reading a value through the `env.lookup` import then costs two stack reads, the start of the table
+//! (the host keeps one per table id) and the value itself.
+//!
+//! Section layout (the linker concatenates the records of all tables), all values are little-endian i64:
+//! `[table_id] [len] [value_0] ... [value_len-1]`
+
+/// Name of the WASM custom section containing all lookup tables.
+pub const LOOKUP_SECTION: &str = "ksplang_lookup";
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+unsafe extern "C" {
+    /// Reads `index`-th value of the lookup table with the given id.
+    #[link_name = "lookup"]
+    fn _lookup(table_id: u32, index: u32) -> i64;
+}
+
+/// A handle to a constant table defined with [lookup_table!](crate::lookup_table).
+#[derive(Copy, Clone)]
+pub struct LookupTable {
+    id: u32,
+    #[cfg(not(target_arch = "wasm32"))]
+    values: &'static [i64],
+    #[cfg(target_arch = "wasm32")]
+    len: usize,
+}
+
+impl LookupTable {
+    #[doc(hidden)]
+    pub const fn new(id: u32, values: &'static [i64]) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        return LookupTable { id, len: values.len() };
+        #[cfg(not(target_arch = "wasm32"))]
+        return LookupTable { id, values };
+    }
+
+    /// Returns the id of this table, as used in the custom section.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Gets the value at the given index. Panics if out of bounds.
+    pub fn get(&self, index: usize) -> i64 {
+        assert!(index < self.len(), "Index out of bounds");
+        unsafe { self.get_unchecked(index) }
+    }
+
+    /// Gets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds. The host does not check it, so out of bounds reads
+    /// return whatever is next on the stack (or crash the ksplang program).
+    #[cfg(target_arch = "wasm32")]
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
+        unsafe { _lookup(self.id, index as u32) }
+    }
+
+    /// Gets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds. The host does not check it, so out of bounds reads
+    /// return whatever is next on the stack (or crash the ksplang program).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
+        unsafe { *self.values.get_unchecked(index) }
+    }
+
+    /// Returns the number of values in the table.
+    pub fn len(&self) -> usize {
+        #[cfg(target_arch = "wasm32")]
+        return self.len;
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.values.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Encodes one table record of the custom section, `BYTES` must be `8 * (values.len() + 2)`.
+#[doc(hidden)]
+pub const fn encode_section<const BYTES: usize>(id: u32, values: &[i64]) -> [u8; BYTES] {
+    assert!(BYTES == 8 * (values.len() + 2), "Invalid lookup table section size");
+    let mut result = [0u8; BYTES];
+    write_i64(&mut result, 0, id as i64);
+    write_i64(&mut result, 1, values.len() as i64);
+    let mut i = 0;
+    while i < values.len() {
+        write_i64(&mut result, i + 2, values[i]);
+        i += 1;
+    }
+    result
+}
+
+const fn write_i64(target: &mut [u8], position: usize, value: i64) {
+    let bytes = value.to_le_bytes();
+    let mut i = 0;
+    while i < 8 {
+        target[position * 8 + i] = bytes[i];
+        i += 1;
+    }
+}
+
+/// Defines a constant lookup table which is placed directly on the ksplang stack by the host.
+///
+/// Every table in the program needs a unique id, ids are small integer literals (the host keeps a table
+/// of offsets indexed by them). Reusing an id within a crate fails to compile with
+/// "symbol `ksplang_lookup_table_id_<id>` is already defined", the host also rejects duplicate ids
+/// when reading the custom section.
+///
+/// ```
+/// common::lookup_table!(POWERS_OF_TEN, 0, [1, 10, 100, 1_000]);
+///
+/// assert_eq!(POWERS_OF_TEN.get(2), 100);
+/// ```
+#[macro_export]
+macro_rules! lookup_table {
+    ($vis:vis $name:ident, $id:literal, [$($value:expr),* $(,)?]) => {
+        $vis const $name: $crate::lookup::LookupTable = {
+            const VALUES: &[i64] = &[$($value),*];
+
+            #[cfg(target_arch = "wasm32")]
+            #[unsafe(link_section = "ksplang_lookup")]
+            #[used]
+            static SECTION: [u8; 8 * (VALUES.len() + 2)] = $crate::lookup::encode_section($id, VALUES);
+
+            // Claims a symbol named after the id, so two tables with the same id collide at compile time.
+            // Not emitted for WASM, where it would become an exported global.
+            #[cfg(not(target_arch = "wasm32"))]
+            #[used]
+            #[unsafe(export_name = concat!("ksplang_lookup_table_id_", $id))]
+            static ID_CLAIM: u8 = 0;
+
+            $crate::lookup::LookupTable::new($id, VALUES)
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::lookup_table!(SQUARES, 3, [0, 1, 4, 9, -16]);
+
+    #[test]
+    fn section_encoding() {
+        let section: [u8; 32] = encode_section(7, &[1, -1]);
+        assert_eq!(section[..8], 7i64.to_le_bytes());
+        assert_eq!(section[8..16], 2i64.to_le_bytes());
+        assert_eq!(section[16..24], 1i64.to_le_bytes());
+        assert_eq!(section[24..], (-1i64).to_le_bytes());
+    }
+
+    #[test]
+    fn table_lookup() {
+        assert_eq!(SQUARES.id(), 3);
+        assert_eq!(SQUARES.len(), 5);
+        assert_eq!(SQUARES.get(4), -16);
+        assert_eq!(unsafe { SQUARES.get_unchecked(2) }, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn index_out_of_bounds() {
+        SQUARES.get(5);
+    }
+}
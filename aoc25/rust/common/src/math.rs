@@ -0,0 +1,266 @@
+//! Number theory built on the ksplang instructions.
+//!
+//! Everything works with plain i64 values and uses the cheap instructions (lensum, gcd, cs, sgn, ...)
+//! instead of generic i64 comparisons. Each function documents whether it is safe for all inputs
+//! or crashes the program on overflow, like the functions in [instructions](crate::instructions).
+
+use crate::failure;
+use crate::instructions::{add_unchecked, and, bitshift, checked_mul, cmp, cs, div_unchecked, gcd_unchecked, is_nonzero, is_zero, lensum, lt, mul_unchecked, negate_unchecked, rem, sgn, subabs_unchecked};
+use crate::raw_i64::RawI64;
+
+/// Bases of the Miller-Rabin test, deterministic for all n < 3.3 * 10^24.
+const MILLER_RABIN_BASES: [i64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Raises `base` to the power of `exp` by squaring.
+/// Result: `base^exp`.
+///
+/// # Safety
+/// This function leads to a program crash if the result overflows i64.
+pub fn pow(base: i64, exp: u32) -> i64 {
+    RawI64::new(base).pow(exp).to_i64()
+}
+
+/// Raises `base` to the power of `exp` by squaring, returns None on overflow.
+/// Result: `base^exp`.
+pub fn checked_pow(base: i64, mut exp: u32) -> Option<RawI64> {
+    let mut base = base;
+    let mut result = 1;
+    loop {
+        if exp & 1 == 1 {
+            result = checked_mul(result, base)?.to_i64();
+        }
+        exp >>= 1;
+        if exp == 0 {
+            return Some(result.into());
+        }
+        base = checked_mul(base, base)?.to_i64();
+    }
+}
+
+/// Returns the base 10 logarithm of `|a|` rounded down, using lensum.
+/// Result: `ilog10(|a|)`.
+///
+/// # Safety
+/// Fails with [INVALID_ARGUMENT](crate::failure::INVALID_ARGUMENT) if a == 0.
+pub fn ilog10(a: i64) -> u32 {
+    let digits = lensum(a, 0);
+    if digits == 0 {
+        failure::fail(failure::INVALID_ARGUMENT);
+    }
+    digits - 1
+}
+
+/// Returns the square root of `a` rounded down, using Newton's method with div.
+/// Result: `isqrt(a)`.
+///
+/// # Safety
+/// Fails with [INVALID_ARGUMENT](crate::failure::INVALID_ARGUMENT) if a is negative.
+pub fn isqrt(a: i64) -> i64 {
+    match sgn(a) {
+        -1 => failure::fail(failure::INVALID_ARGUMENT),
+        0 => return 0,
+        _ => {}
+    }
+    // 10^ceil(digits / 2) is always at least the root, and at most 10^10, so nothing here overflows
+    let mut x = pow(10, lensum(a, 0).div_ceil(2));
+    loop {
+        let next = div_unchecked(add_unchecked(x, div_unchecked(a, x)), 2);
+        if !lt(next, x) {
+            return x;
+        }
+        x = next;
+    }
+}
+
+/// Returns the least common multiple of `|a|` and `|b|`, using gcd.
+/// Result: `lcm(a, b)`, 0 if any of them is 0.
+///
+/// # Safety
+/// This function leads to a program crash if the result overflows i64.
+pub fn lcm(a: i64, b: i64) -> i64 {
+    if is_zero(a) || is_zero(b) {
+        return 0;
+    }
+    let product = mul_unchecked(div_unchecked(a, gcd_unchecked(a, b)), b);
+    if sgn(product) == -1 { negate_unchecked(product) } else { product }
+}
+
+/// Returns the least common multiple of `|a|` and `|b|`, returns None on overflow.
+/// Result: `lcm(a, b)`, 0 if any of them is 0.
+pub fn checked_lcm(a: i64, b: i64) -> Option<RawI64> {
+    if is_zero(a) || is_zero(b) {
+        return Some(0.into());
+    }
+    let gcd = crate::instructions::checked_gcd(a, b)?.to_i64();
+    let product = checked_mul(div_unchecked(a, gcd), b)?.to_i64();
+    if sgn(product) == -1 { crate::instructions::checked_neg(product) } else { Some(product.into()) }
+}
+
+/// Finds the gcd and Bézout coefficients with the extended Euclidean algorithm.
+/// Result: `(g, x, y)` such that `a*x + b*y = g = gcd(a, b)`, g is non-negative.
+///
+/// The coefficients are at most `|b| / g` and `|a| / g` in absolute value.
+///
+/// # Safety
+/// This function leads to a program crash if a or b is [i64::MIN].
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while is_nonzero(r) {
+        let q = div_unchecked(old_r, r);
+        (old_r, r) = (r, add_unchecked(old_r, negate_unchecked(mul_unchecked(q, r))));
+        (old_x, x) = (x, add_unchecked(old_x, negate_unchecked(mul_unchecked(q, x))));
+        (old_y, y) = (y, add_unchecked(old_y, negate_unchecked(mul_unchecked(q, y))));
+    }
+    if sgn(old_r) == -1 {
+        (negate_unchecked(old_r), negate_unchecked(old_x), negate_unchecked(old_y))
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Returns the sum of the decimal digits of `|a|`, using cs, this is safe for all inputs.
+pub fn digit_sum(a: i64) -> u32 {
+    cs(a)
+}
+
+/// Adds two values modulo m without overflowing, both have to be in `0..m`.
+fn add_mod(a: i64, b: i64, m: i64) -> i64 {
+    // m - b is positive, a - (m - b) is the result if it is not negative
+    let wrapped = add_unchecked(a, negate_unchecked(subabs_unchecked(m, b)));
+    if sgn(wrapped) == -1 { add_unchecked(a, b) } else { wrapped }
+}
+
+/// Multiplies two values modulo m without overflowing, both have to be in `0..m`, m has to be positive.
+/// Result: `a * b mod m`.
+///
+/// If the product fits into i64 (checked with lensum), this is a single mul and rem.
+/// Otherwise, it falls back to doubling and adding bit by bit, which is a lot slower.
+pub fn mul_mod(a: i64, b: i64, m: i64) -> i64 {
+    if lensum(a, b) <= 18 {
+        // a * b < 10^18
+        return rem(mul_unchecked(a, b), m);
+    }
+    let mut result = 0;
+    for bit in (0..63).rev() {
+        result = add_mod(result, result, m);
+        if is_nonzero(and(b, bitshift(1, bit))) {
+            result = add_mod(result, a, m);
+        }
+    }
+    result
+}
+
+/// Raises `base` to the power of `exp` modulo m using [mul_mod], this is safe for positive m.
+/// Result: `base^exp mod m`, base has to be in `0..m`.
+pub fn pow_mod(base: i64, mut exp: u64, m: i64) -> i64 {
+    let mut base = base;
+    let mut result = rem(1, m);
+    while exp != 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        exp >>= 1;
+        base = mul_mod(base, base, m);
+    }
+    result
+}
+
+/// Checks whether n is a prime with a deterministic Miller-Rabin test, this is safe for all inputs.
+pub fn is_prime(n: i64) -> bool {
+    if cmp(n, 2) == -1 {
+        return false;
+    }
+    for p in MILLER_RABIN_BASES {
+        if is_zero(rem(n, p)) {
+            return is_zero(subabs_unchecked(n, p));
+        }
+    }
+    // n is odd here, n - 1 = d * 2^s
+    let n_minus_one = add_unchecked(n, -1);
+    let mut d = n_minus_one;
+    let mut s = 0;
+    while is_zero(and(d, 1)) {
+        d = div_unchecked(d, 2);
+        s += 1;
+    }
+    'bases: for a in MILLER_RABIN_BASES {
+        let mut x = pow_mod(a, d as u64, n);
+        if is_zero(subabs_unchecked(x, 1)) || is_zero(subabs_unchecked(x, n_minus_one)) {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if is_zero(subabs_unchecked(x, n_minus_one)) {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn powers_and_logs() {
+        assert_eq!(pow(3, 0), 1);
+        assert_eq!(pow(-2, 63), i64::MIN);
+        assert_eq!(checked_pow(2, 63), None);
+        assert_eq!(checked_pow(10, 18), Some(RawI64::new(1_000_000_000_000_000_000)));
+        assert_eq!(ilog10(1), 0);
+        assert_eq!(ilog10(-999), 2);
+        assert_eq!(ilog10(i64::MIN), 18);
+        assert_eq!(digit_sum(-1234), 10);
+    }
+
+    #[test]
+    fn square_roots_match_std() {
+        for a in [0, 1, 2, 3, 4, 8, 9, 10, 99, 100, 101, 1 << 40, (1 << 40) - 1, 999_999_999_999_999_999, i64::MAX] {
+            assert_eq!(isqrt(a), a.isqrt(), "isqrt({a})");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -4")]
+    fn negative_square_root_fails() {
+        isqrt(-1);
+    }
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(lcm(4, -6), 12);
+        assert_eq!(lcm(0, 5), 0);
+        assert_eq!(checked_lcm(i64::MAX, i64::MAX - 1), None);
+        assert_eq!(checked_lcm(-3, 7), Some(RawI64::new(21)));
+        for (a, b) in [(240, 46), (-240, 46), (0, 5), (5, 0), (17, 17), (i64::MAX, i64::MAX - 1), (-7, -21)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd_unchecked(a, b), "gcd({a}, {b})");
+            assert_eq!(a as i128 * x as i128 + b as i128 * y as i128, g as i128, "bezout({a}, {b})");
+        }
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        let m = i64::MAX - 24; // the largest prime below 2^63
+        assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+        assert_eq!(mul_mod(123_456_789, 987_654_321, 1_000_000_007), 123_456_789 * 987_654_321 % 1_000_000_007);
+        assert_eq!(pow_mod(2, 10, 1000), 24);
+        assert_eq!(pow_mod(5, 0, 1), 0);
+    }
+
+    #[test]
+    fn primes() {
+        let small: Vec<i64> = (-5..60).filter(|&n| is_prime(n)).collect();
+        assert_eq!(small, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59]);
+        assert!(is_prime(1_000_000_007));
+        assert!(is_prime(i64::MAX - 24));
+        assert!(!is_prime(i64::MAX));
+        // Strong pseudoprime to bases 2, 3, 5 and 7
+        assert!(!is_prime(3_215_031_751));
+        assert!(!is_prime(1_000_000_007 * 998_244_353));
+    }
+}
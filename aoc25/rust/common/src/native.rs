@@ -0,0 +1,524 @@
+//! Native implementation of the host environment, used when not compiling to WASM.
+//!
+//! When translated by wasm2ksplang, the `env` and `ksplang` imports are provided by the ksplang runtime.
+//! Here we provide the same functions in plain Rust, so that solutions can be run and tested with `cargo test`.
+//! All of them should behave exactly like the ksplang instructions - this includes crashing on overflows,
+//! which we turn into panics.
+//!
+//! The input and the raw i64 memory are thread local, so tests running in parallel do not interfere.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+thread_local! {
+    static INPUT: RefCell<Vec<i64>> = const { RefCell::new(Vec::new()) };
+    static RAW_MEMORY: RefCell<HashMap<usize, i64>> = RefCell::new(HashMap::new());
+    static TRACES: RefCell<Vec<(String, Vec<i64>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Traces are only printed to stderr if this environment variable is set, e.g. `KSPLANG_TRACE=1 cargo test`.
+pub const TRACE_ENV_VAR: &str = "KSPLANG_TRACE";
+
+static PRINT_TRACES: LazyLock<bool> = LazyLock::new(|| std::env::var_os(TRACE_ENV_VAR).is_some());
+
+/// Replaces the input with the given values and clears the raw i64 memory.
+pub fn set_input_values(values: &[i64]) {
+    INPUT.with_borrow_mut(|input| {
+        input.clear();
+        input.extend_from_slice(values);
+    });
+    RAW_MEMORY.with_borrow_mut(|memory| memory.clear());
+}
+
+/// Replaces the input with the characters of the given string and clears the raw i64 memory.
+///
+/// This is the same representation as ksplang uses for text input, one value per character.
+pub fn set_input_str(input: &str) {
+    let values: Vec<i64> = input.chars().map(|c| c as i64).collect();
+    set_input_values(&values);
+}
+
+/// Runs the exported `solve` function with the given text input, e.g. `run_with_input(EXAMPLE, solve)` in tests.
+pub fn run_with_input<R>(input: &str, solve: extern "C" fn() -> R) -> R {
+    set_input_str(input);
+    solve()
+}
+
+/// Returns all trace records emitted on this thread and clears them.
+pub(crate) fn take_traces() -> Vec<(String, Vec<i64>)> {
+    TRACES.with_borrow_mut(std::mem::take)
+}
+
+fn overflow() -> ! {
+    panic!("ksplang: integer overflow")
+}
+
+fn division_by_zero() -> ! {
+    panic!("ksplang: division by zero")
+}
+
+/// Counterparts of the imports from the `env` module.
+pub(crate) mod env {
+    use super::{INPUT, PRINT_TRACES, RAW_MEMORY, TRACES};
+    use crate::trace::tag_name;
+
+    pub(crate) unsafe fn _fail(code: i64) {
+        panic!("ksplang: failed with code {code}")
+    }
+
+    pub(crate) unsafe fn _trace_tag(tag: i64) {
+        if *PRINT_TRACES {
+            eprintln!("[trace] {}", tag_name(tag));
+        }
+        TRACES.with_borrow_mut(|traces| traces.push((tag_name(tag), Vec::new())));
+    }
+
+    pub(crate) unsafe fn _trace_value(value: i64) {
+        if *PRINT_TRACES {
+            eprintln!("[trace]   {value}");
+        }
+        TRACES.with_borrow_mut(|traces| match traces.last_mut() {
+            Some((_, values)) => values.push(value),
+            None => panic!("trace_value: no trace_tag before it"),
+        });
+    }
+
+    pub(crate) unsafe fn _input_size() -> u32 {
+        INPUT.with_borrow(|input| input.len() as u32)
+    }
+
+    pub(crate) unsafe fn _read_input(index: u32) -> i64 {
+        INPUT.with_borrow(|input| match input.get(index as usize) {
+            Some(value) => *value,
+            None => panic!("read_input: index {index} out of bounds (input size {})", input.len()),
+        })
+    }
+
+    pub(crate) unsafe fn _set_input(value: i64, index: u32) {
+        INPUT.with_borrow_mut(|input| {
+            let size = input.len();
+            match input.get_mut(index as usize) {
+                Some(cell) => *cell = value,
+                None => panic!("set_input: index {index} out of bounds (input size {size})"),
+            }
+        })
+    }
+
+    pub(crate) unsafe fn _save_raw_i64(value: i64, index: usize) {
+        RAW_MEMORY.with_borrow_mut(|memory| memory.insert(index, value));
+    }
+
+    pub(crate) unsafe fn _read_raw_i64(index: usize) -> i64 {
+        // In ksplang, every byte of memory is one i64 stack value. A cell which was never saved
+        // as a raw i64 contains the byte value, so we read the real byte from native memory.
+        match RAW_MEMORY.with_borrow(|memory| memory.get(&index).copied()) {
+            Some(value) => value,
+            None => unsafe { *(index as *const u8) as i64 },
+        }
+    }
+}
+
+/// Counterparts of the imports from the `ksplang` module.
+///
+/// Parameters are in the WASM import order, i.e. the last parameter is on the top of the ksplang stack.
+pub(crate) mod ksplang {
+    use super::{division_by_zero, overflow};
+
+    const FUNKCIA_MOD: i64 = 1_000_000_007;
+
+    pub(crate) unsafe fn _max(a: i64, b: i64) -> i64 {
+        a.max(b)
+    }
+
+    pub(crate) unsafe fn _u_add(a: i64, b: i64) -> i64 {
+        a.checked_add(b).unwrap_or_else(|| overflow())
+    }
+
+    pub(crate) unsafe fn _u_subabs(a: i64, b: i64) -> i64 {
+        i64::try_from(a.abs_diff(b)).unwrap_or_else(|_| overflow())
+    }
+
+    pub(crate) unsafe fn _u_mul(a: i64, b: i64) -> i64 {
+        a.checked_mul(b).unwrap_or_else(|| overflow())
+    }
+
+    pub(crate) unsafe fn _u_curseddiv(divisor: i64, dividend: i64) -> i64 {
+        if divisor == 0 {
+            division_by_zero();
+        }
+        if dividend == i64::MIN && divisor == -1 {
+            overflow();
+        }
+        let rem = dividend % divisor;
+        if rem == 0 { dividend / divisor } else { rem }
+    }
+
+    pub(crate) unsafe fn _u_factorial(a: i64) -> i64 {
+        let n = a.unsigned_abs();
+        if n > 20 {
+            overflow();
+        }
+        (1..=n as i64).product()
+    }
+
+    pub(crate) unsafe fn _u_sgn(a: i64) -> i32 {
+        a.signum() as i32
+    }
+
+    pub(crate) unsafe fn _rem(divisor: i64, dividend: i64) -> i64 {
+        if divisor == 0 {
+            division_by_zero();
+        }
+        // The sign of the divisor is ignored, |i64::MIN| does not fit into i64.
+        ((dividend as i128) % (divisor as i128).abs()) as i64
+    }
+
+    pub(crate) unsafe fn _mod(divisor: i64, dividend: i64) -> i64 {
+        if divisor == 0 {
+            division_by_zero();
+        }
+        ((dividend as i128).rem_euclid((divisor as i128).abs())) as i64
+    }
+
+    pub(crate) unsafe fn _tetr(iters: i64, num: i64) -> i64 {
+        if iters < 0 {
+            panic!("ksplang: negative tetration iterations: {iters}");
+        }
+        match iters {
+            0 => 1,
+            1 => num,
+            _ if num == 0 || num == 1 => 1,
+            _ => {
+                let mut result = num;
+                for _ in 1..iters {
+                    let exponent = u32::try_from(result).unwrap_or_else(|_| overflow());
+                    result = num.checked_pow(exponent).unwrap_or_else(|| overflow());
+                }
+                result
+            }
+        }
+    }
+
+    pub(crate) unsafe fn _cs(a: i64) -> u32 {
+        let mut num = a.unsigned_abs();
+        let mut result = 0;
+        while num != 0 {
+            result += (num % 10) as u32;
+            num /= 10;
+        }
+        result
+    }
+
+    pub(crate) unsafe fn _lensum(a: i64, b: i64) -> u32 {
+        fn len(num: i64) -> u32 {
+            if num == 0 { 0 } else { num.unsigned_abs().ilog10() + 1 }
+        }
+        len(a) + len(b)
+    }
+
+    pub(crate) unsafe fn _bitshift(num: i64, by: i64) -> i64 {
+        if by < 0 {
+            panic!("ksplang: negative bitshift: {by}");
+        }
+        if by >= 64 { 0 } else { num << by }
+    }
+
+    pub(crate) unsafe fn _and(a: i64, b: i64) -> i64 {
+        a & b
+    }
+
+    pub(crate) unsafe fn _gcd(a: i64, b: i64) -> i64 {
+        let mut x = a.unsigned_abs();
+        let mut y = b.unsigned_abs();
+        while y != 0 {
+            (x, y) = (y, x % y);
+        }
+        i64::try_from(x).unwrap_or_else(|_| overflow())
+    }
+
+    pub(crate) unsafe fn _funkcia(a: i64, b: i64) -> u32 {
+        if a == b || (a < 2 && b < 2) {
+            return 0;
+        }
+
+        // Only one of the numbers needs to be factorized, hopefully the smaller one is easier.
+        let (smaller, bigger) = if a < b { (a, b) } else { (b, a) };
+        let mut result = bigger;
+        for (factor, count) in factorize(smaller) {
+            let mut occurs_in_bigger = false;
+            while result % factor == 0 {
+                result /= factor;
+                occurs_in_bigger = true;
+            }
+            if !occurs_in_bigger {
+                for _ in 0..count {
+                    result = result.wrapping_mul(factor % FUNKCIA_MOD) % FUNKCIA_MOD;
+                }
+            }
+        }
+        result %= FUNKCIA_MOD;
+
+        if result == 1 { 0 } else { result as u32 }
+    }
+
+    fn factorize(a: i64) -> Vec<(i64, u32)> {
+        let mut num = a;
+        let mut factors: Vec<(i64, u32)> = Vec::new();
+        let max_factor = (a as f64).sqrt().ceil() as i64;
+        let mut factor = 2;
+        while num > 1 && factor <= max_factor {
+            if num % factor == 0 {
+                match factors.last_mut() {
+                    Some((last, count)) if *last == factor => *count += 1,
+                    _ => factors.push((factor, 1)),
+                }
+                num /= factor;
+            } else {
+                factor += 1;
+            }
+        }
+        if num > 1 {
+            match factors.last_mut() {
+                Some((last, count)) if *last == num => *count += 1,
+                _ => factors.push((num, 1)),
+            }
+        }
+        factors
+    }
+
+    pub(crate) unsafe fn _spanek() {
+        // spanek makes the ksplang program run into a timeout
+        panic!("ksplang: spanek timed out the program");
+    }
+
+    pub(crate) unsafe fn _bulkxor(a: i64, b: i64) -> i32 {
+        ((a > 0) != (b > 0)) as i32
+    }
+
+    pub(crate) unsafe fn _qeq_count(c: i64, b: i64, a: i64) -> i32 {
+        let roots = qeq(a, b, c);
+        // The host uses 0 as a sentinel below the roots, so it must not be a root
+        assert!(c != 0, "qeq_count: c must not be zero");
+        roots.len() as i32
+    }
+
+    pub(crate) unsafe fn _qeq_single(c: i64, b: i64, a: i64) -> i64 {
+        match qeq(a, b, c).as_slice() {
+            [root] => *root,
+            roots => panic!("qeq_single: expected one root, got {}", roots.len()),
+        }
+    }
+
+    pub(crate) unsafe fn _qeq_root(c: i64, b: i64, a: i64, index: i32) -> i64 {
+        match (qeq(a, b, c).as_slice(), index) {
+            ([smaller, _], 0) => *smaller,
+            ([_, larger], 1) => *larger,
+            (roots, _) => panic!("qeq_root: expected two roots and index 0 or 1, got {} roots and index {index}", roots.len()),
+        }
+    }
+
+    /// Integer roots of `a*x^2 + b*x + c = 0`, sorted, crashes like the ksplang instruction.
+    fn qeq(a: i64, b: i64, c: i64) -> Vec<i64> {
+        let (a, b, c) = (a as i128, b as i128, c as i128);
+        let to_i64 = |root: i128| i64::try_from(root).unwrap_or_else(|_| overflow());
+        if a == 0 {
+            if b == 0 {
+                if c == 0 {
+                    panic!("ksplang: qeq with 0 = 0");
+                }
+                return vec![];
+            }
+            return if c % b == 0 { vec![to_i64(-c / b)] } else { vec![] };
+        }
+
+        // The discriminant does not always fit into i128, so we search for the roots instead.
+        // f is monotonic on both sides of the vertex, and all roots are within 1 + max(|b|, |c|) / |a|.
+        let bound: i128 = (1 << 63) + 2;
+        let (numerator, denominator) = (-b, 2 * a);
+        let mut vertex = numerator / denominator;
+        if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+            vertex -= 1;
+        }
+        let vertex = vertex.clamp(-bound - 1, bound);
+
+        let mut roots = Vec::new();
+        if let Some(root) = find_root(a, b, c, -bound, vertex) {
+            roots.push(to_i64(root));
+        }
+        if let Some(root) = find_root(a, b, c, vertex + 1, bound) {
+            roots.push(to_i64(root));
+        }
+        roots
+    }
+
+    /// Binary search for a root of `a*x^2 + b*x + c` on an interval where it is monotonic.
+    fn find_root(a: i128, b: i128, c: i128, mut low: i128, mut high: i128) -> Option<i128> {
+        if low > high {
+            return None;
+        }
+        let sign_low = sign_at(a, b, c, low);
+        if sign_low == 0 {
+            return Some(low);
+        }
+        if sign_at(a, b, c, high) == 0 {
+            return Some(high);
+        }
+        if sign_low == sign_at(a, b, c, high) {
+            return None;
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            let sign_mid = sign_at(a, b, c, mid);
+            if sign_mid == 0 {
+                return Some(mid);
+            }
+            if sign_mid == sign_low {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        None
+    }
+
+    /// Exact sign of `a*x^2 + b*x + c` for |x| <= 2^63 + 2.
+    fn sign_at(a: i128, b: i128, c: i128, x: i128) -> i128 {
+        let t = a * x + b;
+        match x.checked_mul(t) {
+            Some(value) => match value.checked_add(c) {
+                Some(result) => result.signum(),
+                None => value.signum(),
+            },
+            // |x * t| >= 2^127 is way larger than |c|
+            None => x.signum() * t.signum(),
+        }
+    }
+
+    pub(crate) unsafe fn _negate(a: i64) -> i64 {
+        a.checked_neg().unwrap_or_else(|| overflow())
+    }
+
+    pub(crate) unsafe fn _div(divisor: i64, dividend: i64) -> i64 {
+        if divisor == 0 {
+            division_by_zero();
+        }
+        dividend.checked_div(divisor).unwrap_or_else(|| overflow())
+    }
+
+    pub(crate) unsafe fn _is_zero(a: i64) -> i32 {
+        (a == 0) as i32
+    }
+
+    pub(crate) unsafe fn _is_nonzero(a: i64) -> i32 {
+        (a != 0) as i32
+    }
+
+    pub(crate) unsafe fn _eq(a: i64, b: i64) -> i32 {
+        (a == b) as i32
+    }
+
+    pub(crate) unsafe fn _lt(a: i64, b: i64) -> i32 {
+        (a < b) as i32
+    }
+
+    pub(crate) unsafe fn _le(a: i64, b: i64) -> i32 {
+        (a <= b) as i32
+    }
+
+    pub(crate) unsafe fn _select(if_false: i64, if_true: i64, cond: i32) -> i64 {
+        // Rolling the two values by cond, only the parity matters
+        if cond % 2 != 0 { if_true } else { if_false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{add_unchecked, bulkxor, cs, curseddiv_unchecked, div_unchecked, factorial_unchecked, funkcia, gcd_unchecked, lensum, qeq, rem, subabs_unchecked, tetr, QeqRoots};
+    use crate::raw_array::RawFastArray;
+    use crate::{input_size, read_input, read_input_to_string, set_input};
+
+    #[test]
+    fn input_roundtrip() {
+        set_input_str("12\n");
+        assert_eq!(input_size(), 3);
+        assert_eq!(read_input(0), '1' as i64);
+        set_input(2, 42);
+        assert_eq!(read_input(2), 42);
+        set_input(2, '\n' as i64);
+        assert_eq!(read_input_to_string(), "12\n");
+    }
+
+    #[test]
+    fn raw_array_roundtrip() {
+        set_input_str("");
+        let mut array = RawFastArray::new_leaked(3);
+        array.set(1, i64::MIN);
+        assert_eq!(array.get(1), i64::MIN);
+        assert_eq!(array.size(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn raw_array_index_out_of_bounds() {
+        set_input_str("");
+        let array = RawFastArray::new_leaked(3);
+        array[3].set(1);
+    }
+
+    #[test]
+    fn instruction_semantics() {
+        assert_eq!(subabs_unchecked(3, 10), 7);
+        assert_eq!(curseddiv_unchecked(10, 5), 2);
+        assert_eq!(curseddiv_unchecked(10, 3), 1);
+        assert_eq!(factorial_unchecked(-5), 120);
+        assert_eq!(rem(-7, 3), -1);
+        assert_eq!(rem(7, -3), 1);
+        assert_eq!(div_unchecked(-7, 2), -3);
+        assert_eq!(tetr(3, 2), 16);
+        assert_eq!(cs(i64::MIN), 89);
+        assert_eq!(lensum(0, 100), 3);
+        assert_eq!(gcd_unchecked(-12, 18), 6);
+        assert_eq!(funkcia(100, 54), 675);
+        assert_eq!(bulkxor(5, -5), 1);
+        assert_eq!(bulkxor(0, -5), 0);
+    }
+
+    #[test]
+    fn qeq_roots() {
+        assert_eq!(qeq(1, -5, 6), QeqRoots::Two(2, 3));
+        assert_eq!(qeq(1, -4, 4), QeqRoots::One(2));
+        assert_eq!(qeq(2, -5, 3), QeqRoots::One(1));
+        assert_eq!(qeq(1, 0, 1), QeqRoots::None);
+        assert_eq!(qeq(0, 3, -9), QeqRoots::One(3));
+        assert_eq!(qeq(0, 3, -10), QeqRoots::None);
+        assert_eq!(qeq(0, 1, i64::MAX), QeqRoots::One(-i64::MAX));
+        assert_eq!(qeq(-1, i64::MIN, 0), QeqRoots::Two(i64::MIN, 0));
+        assert_eq!(qeq(1, 0, -(3_037_000_499 * 3_037_000_499)), QeqRoots::Two(-3_037_000_499, 3_037_000_499));
+        // 0 is a root, these are solved without the host's qeq_count
+        assert_eq!(qeq(2, -6, 0), QeqRoots::Two(0, 3));
+        assert_eq!(qeq(1, 4, 0), QeqRoots::Two(-4, 0));
+        assert_eq!(qeq(2, 3, 0), QeqRoots::One(0));
+        assert_eq!(qeq(5, 0, 0), QeqRoots::One(0));
+        assert_eq!(qeq(0, 7, 0), QeqRoots::One(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "0 = 0")]
+    fn qeq_zero_equals_zero_panics() {
+        qeq(0, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow")]
+    fn add_overflow_panics() {
+        add_unchecked(i64::MAX, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn rem_by_zero_panics() {
+        rem(1, 0);
+    }
+}
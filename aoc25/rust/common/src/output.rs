@@ -0,0 +1,177 @@
+//! Typed answers returned from `solve()`.
+//!
+//! An [Output] is a pointer to raw i64 memory with the layout `[len] [value_0] ... [value_len-1]`,
+//! the same as [export](crate::export). Days returning it have to be built with `buildWasmOutputProgram`,
+//! which dereferences the pointer after `solve()` returns and leaves exactly the values on the ksplang stack,
+//! `value_0` at the bottom and `value_len-1` on top:
+//!
+//! - [Output::number] - a single value, the stack is `[n]`,
+//! - [Output::numbers] - all values in order, the stack is `[n_0 .. n_len-1]`,
+//! - [Output::text] - one value per character (its code point), which the interpreter prints
+//!   as text in text mode, so the output can be submitted directly.
+//! - [Output::big_int] - the decimal digits of a [BigInt] as text, for answers which do not fit into i64.
+
+use std::fmt::Debug;
+use crate::bigint::BigInt;
+use crate::failure::{OrFail, UNSIGNED_OVERFLOW};
+use crate::raw_i64::RawI64;
+use crate::{KsplangOutput, export};
+
+/// Values returned by the solution function of [ksplang_solve](crate::ksplang_solve),
+/// converted to what the generated `solve()` returns to the host.
+///
+/// Numbers become a single [RawI64], an [Output] is returned as is (the host reads the values it points to).
+pub trait IntoAnswer {
+    type Answer: Answer;
+
+    fn into_answer(self) -> Self::Answer;
+}
+
+/// What `solve()` returns to the host, either [RawI64] or [Output].
+pub trait Answer {
+    /// The answer read natively, examples of [ksplang_solve](crate::ksplang_solve) are compared to this.
+    type Value: PartialEq + Debug;
+
+    fn value(&self) -> Self::Value;
+}
+
+impl Answer for RawI64 {
+    type Value = i64;
+
+    fn value(&self) -> i64 {
+        self.to_i64()
+    }
+}
+
+impl Answer for Output {
+    type Value = Vec<i64>;
+
+    fn value(&self) -> Vec<i64> {
+        self.values()
+    }
+}
+
+impl IntoAnswer for Output {
+    type Answer = Output;
+
+    fn into_answer(self) -> Output {
+        self
+    }
+}
+
+impl IntoAnswer for RawI64 {
+    type Answer = RawI64;
+
+    fn into_answer(self) -> RawI64 {
+        self
+    }
+}
+
+impl IntoAnswer for i64 {
+    type Answer = RawI64;
+
+    fn into_answer(self) -> RawI64 {
+        RawI64::new(self)
+    }
+}
+
+impl IntoAnswer for i32 {
+    type Answer = RawI64;
+
+    fn into_answer(self) -> RawI64 {
+        RawI64::new(self as i64)
+    }
+}
+
+impl IntoAnswer for u32 {
+    type Answer = RawI64;
+
+    fn into_answer(self) -> RawI64 {
+        RawI64::new(self as i64)
+    }
+}
+
+/// ksplang values are i64, larger values fail with [UNSIGNED_OVERFLOW], use [Output::big_int] for those.
+impl IntoAnswer for u64 {
+    type Answer = RawI64;
+
+    fn into_answer(self) -> RawI64 {
+        RawI64::new(i64::try_from(self).or_fail(UNSIGNED_OVERFLOW))
+    }
+}
+
+/// An answer of `solve()`, see the [module docs](self) for the layout.
+#[repr(transparent)]
+pub struct Output(KsplangOutput);
+
+impl Output {
+    /// A single number.
+    pub fn number(value: i64) -> Self {
+        Self::numbers(&[value])
+    }
+
+    /// Multiple numbers, in order.
+    pub fn numbers(values: &[i64]) -> Self {
+        Output(export(values))
+    }
+
+    /// Text, every character is emitted as its code.
+    pub fn text(text: &str) -> Self {
+        let codes: Vec<i64> = text.chars().map(|c| c as i64).collect();
+        Self::numbers(&codes)
+    }
+
+    /// The decimal representation of a big number as text, i.e. the code of every digit, with a leading `-` if negative.
+    pub fn big_int(value: &BigInt) -> Self {
+        let sign = if value.is_negative() { Some('-' as i64) } else { None };
+        let codes: Vec<i64> = sign.into_iter().chain(value.decimal_digits().into_iter().map(|digit| digit + '0' as i64)).collect();
+        Self::numbers(&codes)
+    }
+
+    /// Returns the values of the output, in order.
+    pub fn values(&self) -> Vec<i64> {
+        let pointer = self.0.0 as usize;
+        let len = unsafe { crate::_read_raw_i64(pointer) };
+        (0..len as usize).map(|i| unsafe { crate::_read_raw_i64(pointer + i + 1) }).collect()
+    }
+
+    /// Returns the output interpreted as text, None if any of the values is not a valid character.
+    pub fn to_text(&self) -> Option<String> {
+        self.values().into_iter().map(|value| u32::try_from(value).ok().and_then(char::from_u32)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_layout() {
+        assert_eq!(Output::number(i64::MIN).values(), vec![i64::MIN]);
+        assert_eq!(Output::numbers(&[]).values(), Vec::<i64>::new());
+        assert_eq!(Output::numbers(&[3, -1, 4]).values(), vec![3, -1, 4]);
+
+        let text = Output::text("6,7,Ž");
+        assert_eq!(text.values(), vec!['6' as i64, ',' as i64, '7' as i64, ',' as i64, 'Ž' as i64]);
+        assert_eq!(text.to_text().as_deref(), Some("6,7,Ž"));
+        assert_eq!(Output::number(-1).to_text(), None);
+
+        let mut big = BigInt::from(-i64::MAX);
+        big.mul_small(1000);
+        assert_eq!(Output::big_int(&big).to_text().as_deref(), Some("-9223372036854775807000"));
+        assert_eq!(Output::big_int(&BigInt::new()).values(), vec!['0' as i64]);
+    }
+
+    #[test]
+    fn answers() {
+        assert_eq!(7u32.into_answer().value(), 7);
+        assert_eq!((i64::MAX as u64).into_answer().value(), i64::MAX);
+        assert_eq!(Output::numbers(&[1, 2]).into_answer().value(), vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -3")]
+    fn u64_answer_overflow() {
+        (i64::MAX as u64 + 1).into_answer();
+    }
+}
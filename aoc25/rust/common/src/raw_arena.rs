@@ -0,0 +1,173 @@
+use std::alloc::{Layout, alloc, dealloc};
+use std::cell::Cell;
+use crate::instructions::and;
+use crate::raw_array::RawSliceMut;
+use crate::{_read_raw_i64, _save_raw_i64};
+
+/// A region of raw i64 memory which many arrays can be allocated from.
+///
+/// Allocation is just a bump of an offset. Instead of leaking like
+/// [RawFastArray::new_leaked](crate::raw_array::RawFastArray::new_leaked), the cells are reused:
+/// when a [scope](RawArena::scope) ends or [reset](RawArena::reset) is called, the cells handed out
+/// since then are zeroed, so they hold byte values again and can be safely allocated once more.
+/// The whole region is zeroed before it is returned to the allocator on drop.
+pub struct RawArena {
+    data: *mut u8,
+    capacity: usize,
+    used: Cell<usize>,
+    debug: bool,
+}
+
+impl RawArena {
+    /// Creates an arena with space for `capacity` values.
+    pub fn new(capacity: usize) -> Self {
+        let layout = Layout::from_size_align(capacity.max(1), 1).unwrap();
+        let data = unsafe { alloc(layout) };
+        assert!(!data.is_null(), "Allocation failed");
+        RawArena { data, capacity, used: Cell::new(0), debug: false }
+    }
+
+    /// Creates an arena which checks that no value was left outside the allocated arrays
+    /// whenever cells are released, see [RawArena::dirty_cells]. Panics (crashes in ksplang) if there is one.
+    ///
+    /// The checks read every free cell, use only for debugging.
+    pub fn new_debug(capacity: usize) -> Self {
+        let mut arena = Self::new(capacity);
+        arena.debug = true;
+        arena
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cells currently allocated.
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Allocates an array of `len` values. Panics if the arena is full.
+    ///
+    /// The values are not initialized, they contain whatever byte value the memory had.
+    pub fn alloc(&self, len: usize) -> RawSliceMut<'_> {
+        let offset = self.used.get();
+        assert!(len <= self.capacity - offset, "RawArena is full");
+        self.used.set(offset + len);
+        // Every allocation covers distinct cells, so the mutable views never overlap.
+        unsafe { RawSliceMut::from_raw_parts(self.data.add(offset), len) }
+    }
+
+    /// Allocates an array of `len` values, all set to zero.
+    pub fn alloc_zeroed(&self, len: usize) -> RawSliceMut<'_> {
+        let mut slice = self.alloc(len);
+        slice.fill(0);
+        slice
+    }
+
+    /// Runs `f` with this arena, everything allocated inside is released when it returns.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&RawArena) -> R) -> R {
+        let mark = self.used.get();
+        let result = f(self);
+        self.release_to(mark);
+        result
+    }
+
+    /// Releases all allocations.
+    pub fn reset(&mut self) {
+        self.release_to(0);
+    }
+
+    /// Returns offsets of all free cells which do not contain a byte value (0..=255).
+    ///
+    /// There should never be any, otherwise something wrote out of bounds of its array
+    /// and the value would survive into the next allocation or the allocator.
+    pub fn dirty_cells(&self) -> Vec<usize> {
+        (self.used.get()..self.capacity)
+            .filter(|&i| !is_byte(unsafe { _read_raw_i64(self.data as usize + i) }))
+            .collect()
+    }
+
+    fn release_to(&mut self, mark: usize) {
+        self.zero_to(mark);
+        if self.debug {
+            let dirty = self.dirty_cells();
+            assert!(dirty.is_empty(), "RawArena: free cells hold non-byte values at offsets {dirty:?}");
+        }
+    }
+
+    fn zero_to(&self, mark: usize) {
+        for i in mark..self.used.get() {
+            unsafe { _save_raw_i64(0, self.data as usize + i) }
+        }
+        self.used.set(mark);
+    }
+}
+
+impl Drop for RawArena {
+    fn drop(&mut self) {
+        // No debug check here, a second panic while unwinding from a failed check would abort
+        self.zero_to(0);
+        let layout = Layout::from_size_align(self.capacity.max(1), 1).unwrap();
+        unsafe { dealloc(self.data, layout) }
+    }
+}
+
+fn is_byte(value: i64) -> bool {
+    and(value, !0xFF) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_reuse_memory() {
+        let mut arena = RawArena::new(10);
+        let mut outer = arena.alloc_zeroed(2);
+        outer.set(0, i64::MIN);
+        let first = arena.scope(|arena| {
+            let mut a = arena.alloc(4);
+            let mut b = arena.alloc(4);
+            a.fill(i64::MAX);
+            b.fill(-1);
+            assert_eq!(arena.used(), 10);
+            a.get(3).unwrap() + b.get(0).unwrap()
+        });
+        assert_eq!(first, i64::MAX - 1);
+        assert_eq!(arena.used(), 2);
+        assert!(arena.dirty_cells().is_empty());
+        let reused = arena.alloc(8);
+        assert!(reused.iter().all(|x| x == 0));
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+    }
+
+    #[test]
+    fn reports_dirty_cells() {
+        let arena = RawArena::new(4);
+        let mut array = arena.alloc(1);
+        // Writes past the allocation on purpose, the cell still belongs to the arena
+        unsafe { array.set_unchecked(2, 1000) };
+        assert_eq!(arena.dirty_cells(), vec![2]);
+        // Clean up for drop
+        unsafe { array.set_unchecked(2, 0) };
+    }
+
+    #[test]
+    #[should_panic(expected = "free cells hold non-byte values at offsets [3]")]
+    fn debug_mode_panics_on_dirty_cells() {
+        let mut arena = RawArena::new_debug(4);
+        arena.scope(|arena| {
+            // Writes past the allocation on purpose, the cell still belongs to the arena
+            unsafe { arena.alloc(1).set_unchecked(3, -1) };
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "RawArena is full")]
+    fn full_arena() {
+        let arena = RawArena::new(4);
+        arena.alloc(3);
+        arena.alloc(2);
+    }
+}
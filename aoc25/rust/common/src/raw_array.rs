@@ -1,4 +1,7 @@
-use crate::_read_raw_i64;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut, Range};
+use crate::{_read_raw_i64, _save_raw_i64};
 
 /// A cursed structure which efficiently stores i64 values.
 ///
@@ -21,24 +24,309 @@ impl RawFastArray {
         RawFastArray { data, size }
     }
 
+    /// Gets the i64 value at the given index. Panics if out of bounds.
+    pub fn get(&self, index: usize) -> i64 {
+        assert!(index < self.size, "Index out of bounds");
+        unsafe { self.get_unchecked(index) }
+    }
+
     /// Gets the i64 value at the given index.
     ///
-    /// SAFETY:
-    /// No index checks are performed, anything might happen if out of bounds.
-    pub fn get(&self, index: usize) -> i64 {
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
         unsafe { _read_raw_i64(self.data as usize + index) }
     }
 
+    /// Sets the i64 value at the given index. Panics if out of bounds.
+    pub fn set(&mut self, index: usize, value: i64) {
+        assert!(index < self.size, "Index out of bounds");
+        unsafe { self.set_unchecked(index, value) }
+    }
+
     /// Sets the i64 value at the given index.
     ///
-    /// SAFETY:
-    /// No index checks are performed, anything might happen if out of bounds.
-    pub fn set(&mut self, index: usize, value: i64) {
-        unsafe { crate::_save_raw_i64(value, self.data as usize + index) }
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn set_unchecked(&mut self, index: usize, value: i64) {
+        unsafe { _save_raw_i64(value, self.data as usize + index) }
     }
 
     /// Returns the size of the array.
     pub fn size(&self) -> usize {
         self.size
     }
-}
\ No newline at end of file
+
+    /// Returns a read-only view of the whole array.
+    pub fn as_slice(&self) -> RawSlice<'_> {
+        unsafe { RawSlice::from_raw_parts(self.data, self.size) }
+    }
+
+    /// Returns a mutable view of the whole array.
+    pub fn as_mut_slice(&mut self) -> RawSliceMut<'_> {
+        unsafe { RawSliceMut::from_raw_parts(self.data, self.size) }
+    }
+
+    /// Iterates over all values of the array.
+    pub fn iter(&self) -> RawIter<'_> {
+        self.as_slice().iter()
+    }
+}
+
+impl Index<usize> for RawFastArray {
+    type Output = RawCell;
+
+    /// Panics if out of bounds.
+    fn index(&self, index: usize) -> &RawCell {
+        assert!(index < self.size, "Index out of bounds");
+        unsafe { RawCell::from_ptr(self.data.add(index)) }
+    }
+}
+
+impl IndexMut<usize> for RawFastArray {
+    fn index_mut(&mut self, index: usize) -> &mut RawCell {
+        assert!(index < self.size, "Index out of bounds");
+        unsafe { RawCell::from_mut_ptr(self.data.add(index)) }
+    }
+}
+
+/// A single memory cell holding a raw i64 value, this is what indexing raw arrays returns.
+///
+/// The value is not stored as an i64 in WASM memory, so `&i64` cannot be returned.
+/// Instead, a reference to the one byte which holds the whole value on the ksplang stack is used:
+/// `array[3].get()`, `array[3].set(5)`.
+#[repr(transparent)]
+pub struct RawCell(UnsafeCell<u8>);
+
+impl RawCell {
+    /// # Safety
+    /// The pointer has to point into memory holding raw i64 values which outlives the reference.
+    pub(crate) unsafe fn from_ptr<'a>(ptr: *mut u8) -> &'a RawCell {
+        unsafe { &*(ptr as *const RawCell) }
+    }
+
+    /// # Safety
+    /// Same as [RawCell::from_ptr], and the cell must not be aliased.
+    pub(crate) unsafe fn from_mut_ptr<'a>(ptr: *mut u8) -> &'a mut RawCell {
+        unsafe { &mut *(ptr as *mut RawCell) }
+    }
+
+    pub fn get(&self) -> i64 {
+        unsafe { _read_raw_i64(self.0.get() as usize) }
+    }
+
+    pub fn set(&self, value: i64) {
+        unsafe { _save_raw_i64(value, self.0.get() as usize) }
+    }
+}
+
+/// A borrowed view into raw i64 memory, the equivalent of `&[i64]`.
+///
+/// Raw values do not exist as i64 in WASM memory, so we cannot hand out references to them,
+/// all access goes through [get](RawSlice::get) and friends instead.
+#[derive(Copy, Clone)]
+pub struct RawSlice<'a> {
+    data: *mut u8,
+    len: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl<'a> RawSlice<'a> {
+    /// Creates a view of `len` raw i64 values starting at `data`.
+    ///
+    /// # Safety
+    /// The memory must be allocated and contain raw i64 values for the whole lifetime of the view.
+    pub unsafe fn from_raw_parts(data: *mut u8, len: usize) -> Self {
+        RawSlice { data, len, _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the value at the given index, or None if out of bounds.
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if index < self.len {
+            Some(unsafe { self.get_unchecked(index) })
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
+        unsafe { _read_raw_i64(self.data as usize + index) }
+    }
+
+    pub fn first(&self) -> Option<i64> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<i64> {
+        if self.len == 0 { None } else { self.get(self.len - 1) }
+    }
+
+    /// Returns a view of a part of this slice. Panics if the range is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> RawSlice<'a> {
+        assert!(range.start <= range.end && range.end <= self.len, "Range out of bounds");
+        RawSlice { data: self.data.wrapping_add(range.start), len: range.end - range.start, _marker: PhantomData }
+    }
+
+    pub fn iter(&self) -> RawIter<'a> {
+        RawIter { data: self.data, front: 0, back: self.len, _marker: PhantomData }
+    }
+
+    /// Copies the values into a regular vector (which is backed by byte memory).
+    pub fn to_vec(&self) -> Vec<i64> {
+        self.iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for RawSlice<'a> {
+    type Item = i64;
+    type IntoIter = RawIter<'a>;
+
+    fn into_iter(self) -> RawIter<'a> {
+        self.iter()
+    }
+}
+
+/// A mutable borrowed view into raw i64 memory, the equivalent of `&mut [i64]`.
+pub struct RawSliceMut<'a> {
+    data: *mut u8,
+    len: usize,
+    _marker: PhantomData<&'a mut ()>,
+}
+
+impl<'a> RawSliceMut<'a> {
+    /// Creates a mutable view of `len` raw i64 values starting at `data`.
+    ///
+    /// # Safety
+    /// The memory must be allocated and not accessed through anything else for the lifetime of the view.
+    pub unsafe fn from_raw_parts(data: *mut u8, len: usize) -> Self {
+        RawSliceMut { data, len, _marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> RawSlice<'_> {
+        RawSlice { data: self.data, len: self.len, _marker: PhantomData }
+    }
+
+    /// Reborrows this view, useful for passing it to a function without giving it away.
+    pub fn reborrow(&mut self) -> RawSliceMut<'_> {
+        RawSliceMut { data: self.data, len: self.len, _marker: PhantomData }
+    }
+
+    pub fn get(&self, index: usize) -> Option<i64> {
+        self.as_slice().get(index)
+    }
+
+    /// Gets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
+        unsafe { _read_raw_i64(self.data as usize + index) }
+    }
+
+    /// Sets the value at the given index. Panics if out of bounds.
+    pub fn set(&mut self, index: usize, value: i64) {
+        assert!(index < self.len, "Index out of bounds");
+        unsafe { self.set_unchecked(index, value) }
+    }
+
+    /// Sets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn set_unchecked(&mut self, index: usize, value: i64) {
+        unsafe { _save_raw_i64(value, self.data as usize + index) }
+    }
+
+    /// Swaps two values. Panics if out of bounds.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len && b < self.len, "Index out of bounds");
+        unsafe {
+            let value_a = self.get_unchecked(a);
+            self.set_unchecked(a, self.get_unchecked(b));
+            self.set_unchecked(b, value_a);
+        }
+    }
+
+    pub fn fill(&mut self, value: i64) {
+        for i in 0..self.len {
+            unsafe { self.set_unchecked(i, value) }
+        }
+    }
+
+    /// Returns a mutable view of a part of this slice. Panics if the range is out of bounds.
+    pub fn slice_mut(&mut self, range: Range<usize>) -> RawSliceMut<'_> {
+        assert!(range.start <= range.end && range.end <= self.len, "Range out of bounds");
+        RawSliceMut { data: self.data.wrapping_add(range.start), len: range.end - range.start, _marker: PhantomData }
+    }
+
+    /// Splits the view into two non-overlapping mutable views at the given index.
+    pub fn split_at_mut(self, mid: usize) -> (RawSliceMut<'a>, RawSliceMut<'a>) {
+        assert!(mid <= self.len, "Index out of bounds");
+        (
+            RawSliceMut { data: self.data, len: mid, _marker: PhantomData },
+            RawSliceMut { data: self.data.wrapping_add(mid), len: self.len - mid, _marker: PhantomData },
+        )
+    }
+
+    pub fn iter(&self) -> RawIter<'_> {
+        self.as_slice().iter()
+    }
+}
+
+/// Iterator over values in raw i64 memory.
+pub struct RawIter<'a> {
+    data: *mut u8,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a ()>,
+}
+
+impl Iterator for RawIter<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.front == self.back {
+            return None;
+        }
+        let value = unsafe { _read_raw_i64(self.data as usize + self.front) };
+        self.front += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for RawIter<'_> {
+    fn next_back(&mut self) -> Option<i64> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(unsafe { _read_raw_i64(self.data as usize + self.back) })
+    }
+}
+
+impl ExactSizeIterator for RawIter<'_> {}
@@ -1,5 +1,9 @@
+use std::cmp::Ordering;
 use std::ops;
-use crate::instructions::{add_unchecked, and, div_unchecked, lensum, mul_unchecked, negate_unchecked, rem, sgn, subabs_unchecked};
+use crate::failure;
+use crate::failure::OrFail;
+use crate::instructions;
+use crate::instructions::{add_unchecked, and, bitnot, bitor, bitshift, bitxor, div_unchecked, lensum, max, mul_unchecked, negate_unchecked, rem, sgn, shift_right, subabs_unchecked};
 
 /// A wrapper around raw ksplang i64 values, providing fast but **UNSAFE** arithmetic operations which invoke ksplang instructions directly.
 /// Any overflow except for bitshifts causes a program crash.
@@ -9,12 +13,14 @@ use crate::instructions::{add_unchecked, and, div_unchecked, lensum, mul_uncheck
 /// - adding two values that exceed i64::MAX or go below i64::MIN
 /// - multiplying two values that exceed i64::MAX or go below i64::MIN
 /// - etc.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Ordering is implemented with cheap ksplang instructions as well, it does not use the generic i64 comparison.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(transparent)]
 pub struct RawI64(i64);
 
 impl RawI64 {
-    pub fn new(value: i64) -> Self {
+    pub const fn new(value: i64) -> Self {
         RawI64(value)
     }
 
@@ -35,9 +41,92 @@ impl RawI64 {
         sgn(self.0)
     }
 
+    /// Checks whether the value is zero, see [instructions::is_zero].
+    pub fn is_zero(&self) -> bool {
+        instructions::is_zero(self.0)
+    }
+
+    /// Checks whether the value is not zero, see [instructions::is_nonzero].
+    pub fn is_nonzero(&self) -> bool {
+        instructions::is_nonzero(self.0)
+    }
+
     pub fn subabs(&self, other: RawI64) -> RawI64 {
         subabs_unchecked(self.0, other.0).into()
     }
+
+    /// Returns the absolute value.
+    ///
+    /// # Safety
+    /// Crashes the program for [i64::MIN].
+    pub fn abs(self) -> RawI64 {
+        subabs_unchecked(self.0, 0).into()
+    }
+
+    /// Returns -1, 0 or 1 depending on the sign of the value.
+    pub fn signum(self) -> RawI64 {
+        (sgn(self.0) as i64).into()
+    }
+
+    /// Raises the value to the power of `exp` by repeated squaring.
+    ///
+    /// # Safety
+    /// Crashes the program if the result overflows.
+    pub fn pow(self, mut exp: u32) -> RawI64 {
+        let mut base = self;
+        let mut result = RawI64::new(1);
+        loop {
+            if exp & 1 == 1 {
+                result *= base;
+            }
+            exp >>= 1;
+            if exp == 0 {
+                return result;
+            }
+            // Only squaring when there are more bits left, so this only overflows if the result would
+            base *= base;
+        }
+    }
+
+    /// `self + other`, returns None instead of crashing on overflow.
+    pub fn checked_add(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_add(self.0, other.0)
+    }
+
+    /// `self - other`, returns None instead of crashing on overflow.
+    pub fn checked_sub(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_sub(self.0, other.0)
+    }
+
+    /// `self * other`, returns None instead of crashing on overflow.
+    pub fn checked_mul(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_mul(self.0, other.0)
+    }
+
+    /// `self / other`, returns None instead of crashing on division by zero or overflow.
+    pub fn checked_div(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_div(self.0, other.0)
+    }
+
+    /// `self % other` (like C %), returns None instead of crashing on division by zero.
+    pub fn checked_rem(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_rem(self.0, other.0)
+    }
+
+    /// Euclidean modulo, returns None instead of crashing on division by zero.
+    pub fn checked_rem_euclid(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_euclid_mod(self.0, other.0)
+    }
+
+    /// `-self`, returns None instead of crashing for [i64::MIN].
+    pub fn checked_neg(self) -> Option<RawI64> {
+        instructions::checked_neg(self.0)
+    }
+
+    /// `|self - other|`, returns None instead of crashing on overflow.
+    pub fn checked_subabs(self, other: RawI64) -> Option<RawI64> {
+        instructions::checked_subabs(self.0, other.0)
+    }
 }
 
 impl From<i64> for RawI64 {
@@ -117,6 +206,92 @@ impl ops::Rem for RawI64 {
     }
 }
 
+impl ops::BitOr for RawI64 {
+    type Output = RawI64;
+
+    fn bitor(self, other: RawI64) -> RawI64 {
+        bitor(self.0, other.0).into()
+    }
+}
+
+impl ops::BitXor for RawI64 {
+    type Output = RawI64;
+
+    fn bitxor(self, other: RawI64) -> RawI64 {
+        bitxor(self.0, other.0).into()
+    }
+}
+
+impl ops::Not for RawI64 {
+    type Output = RawI64;
+
+    fn not(self) -> RawI64 {
+        bitnot(self.0).into()
+    }
+}
+
+/// Shifts using the ksplang bitshift instruction, shifting by 64 or more results in 0 (unlike Rust).
+impl ops::Shl<u32> for RawI64 {
+    type Output = RawI64;
+
+    fn shl(self, by: u32) -> RawI64 {
+        bitshift(self.0, by as i64).into()
+    }
+}
+
+/// Arithmetic shift, shifting by 64 or more results in 0 or -1 (unlike Rust).
+impl ops::Shr<u32> for RawI64 {
+    type Output = RawI64;
+
+    fn shr(self, by: u32) -> RawI64 {
+        shift_right(self.0, by as i64).into()
+    }
+}
+
+// The comparison operators map directly onto the branch-free lt/le imports,
+// which translate to a single ksplang comparison each.
+impl PartialOrd for RawI64 {
+    fn partial_cmp(&self, other: &RawI64) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn lt(&self, other: &RawI64) -> bool {
+        instructions::lt(self.0, other.0)
+    }
+
+    fn le(&self, other: &RawI64) -> bool {
+        instructions::le(self.0, other.0)
+    }
+
+    fn gt(&self, other: &RawI64) -> bool {
+        instructions::lt(other.0, self.0)
+    }
+
+    fn ge(&self, other: &RawI64) -> bool {
+        instructions::le(other.0, self.0)
+    }
+}
+
+impl Ord for RawI64 {
+    fn cmp(&self, other: &RawI64) -> Ordering {
+        if instructions::lt(self.0, other.0) {
+            Ordering::Less
+        } else if instructions::eq(self.0, other.0) {
+            Ordering::Equal
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    fn max(self, other: RawI64) -> RawI64 {
+        max(self.0, other.0).into()
+    }
+
+    fn min(self, other: RawI64) -> RawI64 {
+        if instructions::le(self.0, other.0) { self } else { other }
+    }
+}
+
 impl ops::AddAssign for RawI64 {
     fn add_assign(&mut self, other: RawI64) {
         *self = *self + other;
@@ -153,6 +328,306 @@ impl ops::BitAndAssign for RawI64 {
     }
 }
 
+impl ops::BitOrAssign for RawI64 {
+    fn bitor_assign(&mut self, other: RawI64) {
+        *self = *self | other;
+    }
+}
+
+impl ops::BitXorAssign for RawI64 {
+    fn bitxor_assign(&mut self, other: RawI64) {
+        *self = *self ^ other;
+    }
+}
+
+impl ops::ShlAssign<u32> for RawI64 {
+    fn shl_assign(&mut self, by: u32) {
+        *self = *self << by;
+    }
+}
+
+impl ops::ShrAssign<u32> for RawI64 {
+    fn shr_assign(&mut self, by: u32) {
+        *self = *self >> by;
+    }
+}
+
+/// A wrapper around non-negative raw ksplang values, the unsigned counterpart of [RawI64].
+///
+/// ksplang only has signed i64 values, so the domain is `0..=i64::MAX`, not the full u64 range.
+/// Operators use the same fast instructions as [RawI64], leaving the domain crashes the program
+/// instead of silently misbehaving:
+/// - results above i64::MAX crash just like overflows of [RawI64],
+/// - results below zero (subtraction, shifting into the sign bit) [fail](crate::failure::fail) with
+///   [UNSIGNED_UNDERFLOW](crate::failure::UNSIGNED_UNDERFLOW) or [UNSIGNED_OVERFLOW](crate::failure::UNSIGNED_OVERFLOW).
+///
+/// Values above i64::MAX can be handled explicitly with [RawU64::widening_add], [RawU64::widening_mul]
+/// and [RawU64::split_hi_lo].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct RawU64(i64);
+
+impl RawU64 {
+    /// Creates a new value, fails with [UNSIGNED_OVERFLOW](crate::failure::UNSIGNED_OVERFLOW) above i64::MAX.
+    pub fn new(value: u64) -> Self {
+        RawU64::checked_new(value).or_fail(failure::UNSIGNED_OVERFLOW)
+    }
+
+    /// Creates a new value, returns None above i64::MAX.
+    pub fn checked_new(value: u64) -> Option<Self> {
+        // Values above i64::MAX have the sign bit set
+        if sgn(value as i64) == -1 { None } else { Some(RawU64(value as i64)) }
+    }
+
+    /// Creates a new value from an i64 which is known to be non-negative.
+    ///
+    /// # Safety
+    /// Negative values break the invariants, operators may return garbage instead of crashing.
+    pub fn from_i64_unchecked(value: i64) -> Self {
+        RawU64(value)
+    }
+
+    pub fn to_u64(self) -> u64 {
+        self.0 as u64
+    }
+
+    pub fn to_i64(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_raw_i64(self) -> RawI64 {
+        RawI64(self.0)
+    }
+
+    /// Returns the number of decimal digits in the value, see [RawI64::digit_len].
+    pub fn digit_len(self) -> u32 {
+        lensum(self.0, 0)
+    }
+
+    /// `|self - other|`, this never leaves the domain.
+    pub fn abs_diff(self, other: RawU64) -> RawU64 {
+        RawU64(subabs_unchecked(self.0, other.0))
+    }
+
+    /// `self + other`, returns None instead of crashing above i64::MAX.
+    pub fn checked_add(self, other: RawU64) -> Option<RawU64> {
+        instructions::checked_add(self.0, other.0).map(|x| RawU64(x.0))
+    }
+
+    /// `self - other`, returns None instead of failing below zero.
+    pub fn checked_sub(self, other: RawU64) -> Option<RawU64> {
+        let result = add_unchecked(self.0, negate_unchecked(other.0));
+        if sgn(result) == -1 { None } else { Some(RawU64(result)) }
+    }
+
+    /// `self * other`, returns None instead of crashing above i64::MAX.
+    pub fn checked_mul(self, other: RawU64) -> Option<RawU64> {
+        instructions::checked_mul(self.0, other.0).map(|x| RawU64(x.0))
+    }
+
+    /// `self / other`, returns None instead of crashing on division by zero.
+    pub fn checked_div(self, other: RawU64) -> Option<RawU64> {
+        instructions::checked_div(self.0, other.0).map(|x| RawU64(x.0))
+    }
+
+    /// `self + other` in the full u64 range.
+    pub fn widening_add(self, other: RawU64) -> u64 {
+        match self.checked_add(other) {
+            Some(result) => result.to_u64(),
+            // Both halves are in -2^62..2^62, so the sum does not overflow, it is the result minus 2^63
+            None => add_unchecked(add_unchecked(self.0, -(1 << 62)), add_unchecked(other.0, -(1 << 62))) as u64 + (1 << 63),
+        }
+    }
+
+    /// `self * other` in the full 128-bit range, returns `(high, low)` 64-bit halves.
+    ///
+    /// This falls back to generic (slow) 128-bit arithmetic only if the product leaves the domain.
+    pub fn widening_mul(self, other: RawU64) -> (u64, u64) {
+        match self.checked_mul(other) {
+            Some(result) => (0, result.to_u64()),
+            None => {
+                let result = self.0 as u128 * other.0 as u128;
+                ((result >> 64) as u64, result as u64)
+            }
+        }
+    }
+
+    /// Splits any u64 into its high and low 32 bits, both of which fit into the domain.
+    pub fn split_hi_lo(value: u64) -> (RawU64, RawU64) {
+        let value = value as i64;
+        // The arithmetic shift copies the sign bit into the high bits, the mask drops them again
+        (RawU64(and(shift_right(value, 32), 0xFFFF_FFFF)), RawU64(and(value, 0xFFFF_FFFF)))
+    }
+
+    /// Joins values created by [RawU64::split_hi_lo], `hi * 2^32 + lo`.
+    pub fn join_hi_lo(hi: RawU64, lo: RawU64) -> u64 {
+        bitor(bitshift(hi.0, 32), lo.0) as u64
+    }
+}
+
+impl TryFrom<u64> for RawU64 {
+    type Error = ();
+
+    fn try_from(value: u64) -> Result<Self, ()> {
+        RawU64::checked_new(value).ok_or(())
+    }
+}
+
+impl From<RawU64> for u64 {
+    fn from(value: RawU64) -> Self {
+        value.to_u64()
+    }
+}
+
+impl From<RawU64> for RawI64 {
+    fn from(value: RawU64) -> Self {
+        value.to_raw_i64()
+    }
+}
+
+impl ops::Add for RawU64 {
+    type Output = RawU64;
+
+    fn add(self, other: RawU64) -> RawU64 {
+        RawU64(add_unchecked(self.0, other.0))
+    }
+}
+
+impl ops::Sub for RawU64 {
+    type Output = RawU64;
+
+    /// Fails with [UNSIGNED_UNDERFLOW](crate::failure::UNSIGNED_UNDERFLOW) if `other > self`.
+    fn sub(self, other: RawU64) -> RawU64 {
+        self.checked_sub(other).or_fail(failure::UNSIGNED_UNDERFLOW)
+    }
+}
+
+impl ops::Mul for RawU64 {
+    type Output = RawU64;
+
+    fn mul(self, other: RawU64) -> RawU64 {
+        RawU64(mul_unchecked(self.0, other.0))
+    }
+}
+
+impl ops::Div for RawU64 {
+    type Output = RawU64;
+
+    fn div(self, other: RawU64) -> RawU64 {
+        RawU64(div_unchecked(self.0, other.0))
+    }
+}
+
+impl ops::Rem for RawU64 {
+    type Output = RawU64;
+
+    fn rem(self, other: RawU64) -> RawU64 {
+        RawU64(rem(self.0, other.0))
+    }
+}
+
+impl ops::BitAnd for RawU64 {
+    type Output = RawU64;
+
+    fn bitand(self, other: RawU64) -> RawU64 {
+        RawU64(and(self.0, other.0))
+    }
+}
+
+impl ops::BitOr for RawU64 {
+    type Output = RawU64;
+
+    fn bitor(self, other: RawU64) -> RawU64 {
+        RawU64(bitor(self.0, other.0))
+    }
+}
+
+impl ops::BitXor for RawU64 {
+    type Output = RawU64;
+
+    fn bitxor(self, other: RawU64) -> RawU64 {
+        RawU64(bitxor(self.0, other.0))
+    }
+}
+
+/// Fails with [UNSIGNED_OVERFLOW](crate::failure::UNSIGNED_OVERFLOW) if a bit is shifted into the sign bit
+/// or past it. Unlike in Rust, no bits are lost silently.
+impl ops::Shl<u32> for RawU64 {
+    type Output = RawU64;
+
+    fn shl(self, by: u32) -> RawU64 {
+        if instructions::lt(shift_right(i64::MAX, by as i64), self.0) {
+            failure::fail(failure::UNSIGNED_OVERFLOW);
+        }
+        RawU64(bitshift(self.0, by as i64))
+    }
+}
+
+/// Shifting by 63 or more results in 0.
+impl ops::Shr<u32> for RawU64 {
+    type Output = RawU64;
+
+    fn shr(self, by: u32) -> RawU64 {
+        RawU64(shift_right(self.0, by as i64))
+    }
+}
+
+impl PartialOrd for RawU64 {
+    fn partial_cmp(&self, other: &RawU64) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RawU64 {
+    fn cmp(&self, other: &RawU64) -> Ordering {
+        if instructions::lt(self.0, other.0) {
+            Ordering::Less
+        } else if instructions::eq(self.0, other.0) {
+            Ordering::Equal
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    fn max(self, other: RawU64) -> RawU64 {
+        RawU64(max(self.0, other.0))
+    }
+
+    fn min(self, other: RawU64) -> RawU64 {
+        if instructions::le(self.0, other.0) { self } else { other }
+    }
+}
+
+impl ops::AddAssign for RawU64 {
+    fn add_assign(&mut self, other: RawU64) {
+        *self = *self + other;
+    }
+}
+
+impl ops::SubAssign for RawU64 {
+    fn sub_assign(&mut self, other: RawU64) {
+        *self = *self - other;
+    }
+}
+
+impl ops::MulAssign for RawU64 {
+    fn mul_assign(&mut self, other: RawU64) {
+        *self = *self * other;
+    }
+}
+
+impl ops::DivAssign for RawU64 {
+    fn div_assign(&mut self, other: RawU64) {
+        *self = *self / other;
+    }
+}
+
+impl ops::RemAssign for RawU64 {
+    fn rem_assign(&mut self, other: RawU64) {
+        *self = *self % other;
+    }
+}
+
 /// Important: if to > from is used, this will run forever!
 pub fn iter_non_empty_range_inclusive(from: RawI64, to: RawI64) -> InclusiveRawI64RangeIter {
     InclusiveRawI64RangeIter {
@@ -170,7 +645,7 @@ impl Iterator for InclusiveRawI64RangeIter {
     type Item = RawI64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current.subabs(self.to) == 0.into() {
+        if self.current.subabs(self.to).is_zero() {
             None
         } else {
             let result = self.current;
@@ -196,7 +671,7 @@ impl Iterator for InclusiveRawI64RangeRevIter {
     type Item = RawI64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current.subabs(self.to) == 0.into() {
+        if self.current.subabs(self.to).is_zero() {
             None
         } else {
             let result = self.current;
@@ -206,3 +681,77 @@ impl Iterator for InclusiveRawI64RangeRevIter {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pow() {
+        assert_eq!(RawI64::new(10).pow(18), RawI64::new(1_000_000_000_000_000_000));
+        assert_eq!(RawI64::new(-2).pow(63), RawI64::new(i64::MIN));
+        assert_eq!(RawI64::new(7).pow(0), RawI64::new(1));
+    }
+
+    #[test]
+    fn ordering() {
+        let mut values: Vec<RawI64> = [5, i64::MIN, -3, i64::MAX, 0, -3].into_iter().map(RawI64::new).collect();
+        values.sort();
+        let values: Vec<i64> = values.into_iter().map(|x| x.to_i64()).collect();
+        assert_eq!(values, [i64::MIN, -3, -3, 0, 5, i64::MAX]);
+        assert_eq!(RawI64::new(-4).max(RawI64::new(2)), RawI64::new(2));
+        assert_eq!(RawI64::new(i64::MIN).min(RawI64::new(i64::MAX)), RawI64::new(i64::MIN));
+        let (low, high) = (RawI64::new(i64::MIN), RawI64::new(i64::MAX));
+        assert_eq!([low < high, low <= high, high > low, high >= high], [true; 4]);
+        assert_eq!([high < high, low > high, high <= low, low >= high], [false; 4]);
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(RawI64::new(3) << 62, RawI64::new(i64::MIN + (1 << 62)));
+        assert_eq!(RawI64::new(-9) >> 1, RawI64::new(-5));
+        assert_eq!(RawI64::new(-1).abs(), RawI64::new(1));
+        assert_eq!(RawI64::new(-9).signum(), RawI64::new(-1));
+    }
+
+    #[test]
+    fn unsigned_arithmetic() {
+        let big = RawU64::new(i64::MAX as u64);
+        assert_eq!(RawU64::checked_new(i64::MAX as u64 + 1), None);
+        assert_eq!((RawU64::new(7) - RawU64::new(7)).to_u64(), 0);
+        assert_eq!(RawU64::new(3).checked_sub(RawU64::new(4)), None);
+        assert_eq!(RawU64::new(3).abs_diff(RawU64::new(10)).to_u64(), 7);
+        assert!(RawU64::new(3) < big && RawU64::new(0) < RawU64::new(1));
+        assert_eq!((RawU64::new(1) << 62).to_u64(), 1 << 62);
+        assert_eq!((big >> 62).to_u64(), 1);
+
+        assert_eq!(big.widening_add(big), u64::MAX - 1);
+        assert_eq!(RawU64::new(5).widening_add(RawU64::new(6)), 11);
+        assert_eq!(big.widening_mul(big), (((i64::MAX as u128).pow(2) >> 64) as u64, (i64::MAX as u128).pow(2) as u64));
+        assert_eq!(RawU64::new(1 << 20).widening_mul(RawU64::new(1 << 20)), (0, 1 << 40));
+
+        let (hi, lo) = RawU64::split_hi_lo(u64::MAX - 5);
+        assert_eq!((hi.to_u64(), lo.to_u64()), (0xFFFF_FFFF, 0xFFFF_FFFA));
+        assert_eq!(RawU64::join_hi_lo(hi, lo), u64::MAX - 5);
+        let (hi, lo) = RawU64::split_hi_lo(0x1234_5678_9ABC_DEF0);
+        assert_eq!((hi.to_u64(), lo.to_u64()), (0x1234_5678, 0x9ABC_DEF0));
+        assert_eq!(RawU64::join_hi_lo(hi, lo), 0x1234_5678_9ABC_DEF0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -2")]
+    fn unsigned_underflow_fails() {
+        let _ = RawU64::new(1) - RawU64::new(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -3")]
+    fn unsigned_shift_into_sign_fails() {
+        let _ = RawU64::new(1) << 63;
+    }
+
+    #[test]
+    #[should_panic(expected = "ksplang: failed with code -3")]
+    fn unsigned_shift_past_sign_fails() {
+        let _ = RawU64::new(4) << 62;
+    }
+}
@@ -1,7 +1,12 @@
-use crate::instructions::{add_unchecked, mul_unchecked, subabs_unchecked};
+use std::ops::Range;
+use crate::instructions::{add_unchecked, checked_add, checked_mul, checked_neg, checked_sub, le, mul_unchecked, negate_unchecked, subabs_unchecked};
 use crate::raw_i64::RawI64;
-use crate::read_input;
+use crate::{input_size, read_input, read_input_raw};
 
+/// Parses an u32 from the input starting at input_pos until the terminator character is found.
+///
+/// # Safety
+/// In case of invalid input (non-digit characters before the terminator), the behavior is undefined.
 #[inline]
 pub unsafe fn parse_u32_unchecked(input_pos: &mut u32, terminator: char) -> u32 {
     let mut result = 0;
@@ -17,6 +22,8 @@ pub unsafe fn parse_u32_unchecked(input_pos: &mut u32, terminator: char) -> u32
 }
 
 /// Parses an u64 from the input starting at input_pos until the terminator character is found.
+///
+/// # Safety
 /// In case of invalid input (non-digit characters before the terminator), the behavior is undefined.
 #[inline]
 pub unsafe fn parse_u64_unchecked(input_pos: &mut u32, terminator: char) -> u64 {
@@ -53,5 +60,243 @@ pub fn find_next_unsafe(mut from_index: u32, char: char) -> u32 {
 /// Arbitrary i64 comparison is fairly slow, so if we know the i64 is a valid character,
 /// we can optimize the comparison.
 pub fn is_char(raw_i64: RawI64, c: char) -> bool {
-    raw_i64.subabs((c as i64).into()) == 0.into()
+    raw_i64.subabs((c as i64).into()).is_zero()
+}
+
+/// Error of the checked [InputCursor] methods, `pos` is the input position where it happened.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input ended before the expected character or number.
+    UnexpectedEnd { pos: u32 },
+    /// A different character was found than expected (or a non-digit where a number should be).
+    UnexpectedChar { pos: u32, found: i64 },
+    /// The number does not fit into i64.
+    Overflow { pos: u32 },
+}
+
+/// A position in the input with parsing helpers.
+///
+/// The input is expected to be text (every value is a character). All methods handle the end
+/// of input, so there is no need to add terminators to the input.
+///
+/// The `_unchecked` variants do not validate the input and are faster, on invalid input they return
+/// garbage or crash. The checked variants return a [ParseError] instead.
+pub struct InputCursor {
+    pos: u32,
+    size: u32,
+}
+
+impl InputCursor {
+    /// Creates a cursor at the start of the input.
+    pub fn new() -> Self {
+        Self::at(0)
+    }
+
+    /// Creates a cursor at the given position of the input.
+    pub fn at(pos: u32) -> Self {
+        InputCursor { pos, size: input_size() }
+    }
+
+    /// Returns the current position in the input.
+    pub fn pos(&self) -> u32 {
+        self.pos
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos >= self.size
+    }
+
+    /// Returns the current character without moving, or None at the end of input.
+    pub fn peek(&self) -> Option<RawI64> {
+        if self.at_end() { None } else { Some(read_input_raw(self.pos)) }
+    }
+
+    /// Returns the current character and moves past it, or None at the end of input.
+    pub fn next_char(&mut self) -> Option<RawI64> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    /// Moves by `count` characters without looking at them.
+    pub fn skip(&mut self, count: u32) {
+        self.pos += count;
+    }
+
+    /// Moves past all characters for which `pred` is true, returns how many were skipped.
+    pub fn skip_while(&mut self, pred: impl Fn(RawI64) -> bool) -> u32 {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !pred(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    /// Moves past the expected character.
+    pub fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.peek() {
+            None => Err(ParseError::UnexpectedEnd { pos: self.pos }),
+            Some(c) if is_char(c, expected) => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(ParseError::UnexpectedChar { pos: self.pos, found: c.to_i64() }),
+        }
+    }
+
+    /// Moves past a character which is expected to be there, without checking it.
+    pub fn expect_unchecked(&mut self, _expected: char) {
+        self.pos += 1;
+    }
+
+    /// Returns the input positions up to the next `terminator` (or the end of input) and moves past the terminator.
+    pub fn take_until(&mut self, terminator: char) -> Range<u32> {
+        let start = self.pos;
+        self.skip_while(|c| !is_char(c, terminator));
+        let end = self.pos;
+        if !self.at_end() {
+            self.pos += 1;
+        }
+        start..end
+    }
+
+    /// Parses a non-negative number, stops at the first non-digit character (which is not consumed).
+    ///
+    /// ksplang values are i64, so the domain is `0..=i64::MAX`, larger numbers are an [Overflow](ParseError::Overflow).
+    pub fn parse_u64(&mut self) -> Result<u64, ParseError> {
+        let start = self.pos;
+        let mut result = 0i64;
+        while let Some(digit) = self.peek().and_then(digit_value) {
+            result = checked_mul(result, 10)
+                .and_then(|r| checked_add(r.to_i64(), digit))
+                .ok_or(ParseError::Overflow { pos: self.pos })?
+                .to_i64();
+            self.pos += 1;
+        }
+        self.check_digits_found(start)?;
+        Ok(result as u64)
+    }
+
+    /// Parses a non-negative number, stops at the first non-digit character (which is not consumed).
+    ///
+    /// Like [parse_u64](InputCursor::parse_u64), the number has to be in `0..=i64::MAX`.
+    pub fn parse_u64_unchecked(&mut self) -> u64 {
+        let mut result = 0i64;
+        while let Some(digit) = self.peek().and_then(digit_value) {
+            result = add_unchecked(mul_unchecked(result, 10), digit);
+            self.pos += 1;
+        }
+        result as u64
+    }
+
+    /// Parses a number with an optional `-` sign, stops at the first non-digit character (which is not consumed).
+    pub fn parse_i64(&mut self) -> Result<i64, ParseError> {
+        let negative = self.skip_minus();
+        let start = self.pos;
+        // Accumulated as a negative number, so that i64::MIN can be parsed too
+        let mut result = 0i64;
+        while let Some(digit) = self.peek().and_then(digit_value) {
+            result = checked_mul(result, 10)
+                .and_then(|r| checked_sub(r.to_i64(), digit))
+                .ok_or(ParseError::Overflow { pos: self.pos })?
+                .to_i64();
+            self.pos += 1;
+        }
+        self.check_digits_found(start)?;
+        if negative {
+            Ok(result)
+        } else {
+            checked_neg(result).map(|r| r.to_i64()).ok_or(ParseError::Overflow { pos: self.pos })
+        }
+    }
+
+    /// Parses a number with an optional `-` sign, stops at the first non-digit character (which is not consumed).
+    pub fn parse_i64_unchecked(&mut self) -> i64 {
+        let negative = self.skip_minus();
+        let result = self.parse_u64_unchecked() as i64;
+        if negative { negate_unchecked(result) } else { result }
+    }
+
+    fn skip_minus(&mut self) -> bool {
+        if matches!(self.peek(), Some(c) if is_char(c, '-')) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn check_digits_found(&self, start: u32) -> Result<(), ParseError> {
+        if self.pos != start {
+            return Ok(());
+        }
+        match self.peek() {
+            None => Err(ParseError::UnexpectedEnd { pos: self.pos }),
+            Some(c) => Err(ParseError::UnexpectedChar { pos: self.pos, found: c.to_i64() }),
+        }
+    }
+}
+
+impl Default for InputCursor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the value of a digit character, None for anything else.
+///
+/// The whole i64 is compared, a cell rewritten to e.g. `(1 << 32) + '5'` is not a digit.
+fn digit_value(c: RawI64) -> Option<i64> {
+    let c = c.to_i64();
+    if le('0' as i64, c) && le(c, '9' as i64) { Some(add_unchecked(c, -('0' as i64))) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::{set_input_str, set_input_values};
+
+    #[test]
+    fn cursor_parsing() {
+        set_input_str("12-34,-9223372036854775808 x\n7");
+        let mut cursor = InputCursor::new();
+        assert_eq!(cursor.parse_u64(), Ok(12));
+        assert_eq!(cursor.expect(','), Err(ParseError::UnexpectedChar { pos: 2, found: '-' as i64 }));
+        cursor.expect_unchecked('-');
+        assert_eq!(cursor.parse_u64_unchecked(), 34);
+        assert_eq!(cursor.expect(','), Ok(()));
+        assert_eq!(cursor.parse_i64(), Ok(i64::MIN));
+        assert_eq!(cursor.skip_while(|c| is_char(c, ' ')), 1);
+        assert_eq!(cursor.parse_i64(), Err(ParseError::UnexpectedChar { pos: 27, found: 'x' as i64 }));
+        assert_eq!(cursor.take_until('\n'), 27..28);
+        assert_eq!(cursor.peek(), Some(RawI64::new('7' as i64)));
+        assert_eq!(cursor.parse_i64_unchecked(), 7);
+        assert!(cursor.at_end());
+        assert_eq!(cursor.parse_u64(), Err(ParseError::UnexpectedEnd { pos: 30 }));
+        assert_eq!(cursor.expect('\n'), Err(ParseError::UnexpectedEnd { pos: 30 }));
+        assert_eq!(cursor.take_until('\n'), 30..30);
+    }
+
+    #[test]
+    fn cursor_overflow() {
+        set_input_str("9223372036854775808 -9223372036854775809");
+        let mut cursor = InputCursor::new();
+        assert_eq!(cursor.parse_i64(), Err(ParseError::Overflow { pos: 19 }));
+        cursor.skip(1);
+        assert_eq!(cursor.parse_u64(), Err(ParseError::UnexpectedChar { pos: 20, found: '-' as i64 }));
+        assert_eq!(cursor.parse_i64(), Err(ParseError::Overflow { pos: 39 }));
+    }
+
+    #[test]
+    fn non_characters_are_not_digits() {
+        set_input_values(&[(1 << 32) + '5' as i64, '7' as i64, i64::MIN]);
+        let mut cursor = InputCursor::new();
+        assert_eq!(cursor.parse_u64(), Err(ParseError::UnexpectedChar { pos: 0, found: (1 << 32) + '5' as i64 }));
+        cursor.skip(1);
+        assert_eq!(cursor.parse_i64(), Ok(7));
+        assert_eq!(cursor.peek(), Some(RawI64::new(i64::MIN)));
+    }
 }
@@ -0,0 +1,270 @@
+use std::alloc::{Layout, alloc, dealloc};
+use std::ops::{Index, IndexMut};
+use crate::raw_array::{RawCell, RawIter, RawSlice, RawSliceMut};
+use crate::{_read_raw_i64, _save_raw_i64};
+
+/// A growable array of i64 values, the raw memory equivalent of `Vec<i64>`.
+///
+/// Every value takes up a single memory cell (a `Vec<i64>` needs 8 of them).
+///
+/// Unlike [RawFastArray](crate::raw_array::RawFastArray), the memory is freed on drop.
+/// Before any memory is returned to the allocator, all cells which ever held a value are zeroed,
+/// so the allocator never sees a value which does not fit into a byte.
+pub struct RawFastVec {
+    data: *mut u8,
+    len: usize,
+    capacity: usize,
+    /// High-water mark of used cells, everything from here on still holds a byte value.
+    touched: usize,
+}
+
+impl RawFastVec {
+    /// Creates an empty vector, does not allocate.
+    pub fn new() -> Self {
+        RawFastVec { data: std::ptr::null_mut(), len: 0, capacity: 0, touched: 0 }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut vec = Self::new();
+        vec.reserve(capacity);
+        vec
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Makes sure there is space for at least `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len + additional;
+        if required <= self.capacity {
+            return;
+        }
+        let new_capacity = required.max(self.capacity * 2).max(4);
+        let layout = Layout::from_size_align(new_capacity, 1).unwrap();
+        let new_data = unsafe { alloc(layout) };
+        assert!(!new_data.is_null(), "Allocation failed");
+        for i in 0..self.len {
+            unsafe { _save_raw_i64(_read_raw_i64(self.data as usize + i), new_data as usize + i) }
+        }
+        self.free();
+        self.data = new_data;
+        self.capacity = new_capacity;
+        self.touched = self.len;
+    }
+
+    pub fn push(&mut self, value: i64) {
+        if self.len == self.capacity {
+            self.reserve(1);
+        }
+        unsafe { _save_raw_i64(value, self.data as usize + self.len) }
+        self.len += 1;
+        self.touched = self.touched.max(self.len);
+    }
+
+    pub fn pop(&mut self) -> Option<i64> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { _read_raw_i64(self.data as usize + self.len) })
+    }
+
+    /// Shortens the vector, does nothing if it is already shorter. Capacity is kept.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Gets the value at the given index, or None if out of bounds.
+    pub fn get(&self, index: usize) -> Option<i64> {
+        self.as_slice().get(index)
+    }
+
+    /// Gets the value at the given index.
+    ///
+    /// # Safety
+    /// The index must be in bounds, no index checks are performed.
+    pub unsafe fn get_unchecked(&self, index: usize) -> i64 {
+        unsafe { _read_raw_i64(self.data as usize + index) }
+    }
+
+    /// Sets the value at the given index. Panics if out of bounds.
+    pub fn set(&mut self, index: usize, value: i64) {
+        assert!(index < self.len, "Index out of bounds");
+        unsafe { _save_raw_i64(value, self.data as usize + index) }
+    }
+
+    pub fn first(&self) -> Option<i64> {
+        self.as_slice().first()
+    }
+
+    pub fn last(&self) -> Option<i64> {
+        self.as_slice().last()
+    }
+
+    pub fn as_slice(&self) -> RawSlice<'_> {
+        unsafe { RawSlice::from_raw_parts(self.data, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> RawSliceMut<'_> {
+        unsafe { RawSliceMut::from_raw_parts(self.data, self.len) }
+    }
+
+    pub fn iter(&self) -> RawIter<'_> {
+        self.as_slice().iter()
+    }
+
+    /// Zeroes all touched cells and returns the memory to the allocator.
+    fn free(&mut self) {
+        if self.capacity == 0 {
+            return;
+        }
+        for i in 0..self.touched {
+            unsafe { _save_raw_i64(0, self.data as usize + i) }
+        }
+        let layout = Layout::from_size_align(self.capacity, 1).unwrap();
+        unsafe { dealloc(self.data, layout) }
+    }
+}
+
+impl Default for RawFastVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RawFastVec {
+    fn drop(&mut self) {
+        self.free();
+    }
+}
+
+impl Clone for RawFastVec {
+    fn clone(&self) -> Self {
+        let mut vec = Self::with_capacity(self.len);
+        vec.extend(self.iter());
+        vec
+    }
+}
+
+impl std::fmt::Debug for RawFastVec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Index<usize> for RawFastVec {
+    type Output = RawCell;
+
+    /// Panics if out of bounds.
+    fn index(&self, index: usize) -> &RawCell {
+        assert!(index < self.len, "Index out of bounds");
+        unsafe { RawCell::from_ptr(self.data.add(index)) }
+    }
+}
+
+impl IndexMut<usize> for RawFastVec {
+    fn index_mut(&mut self, index: usize) -> &mut RawCell {
+        assert!(index < self.len, "Index out of bounds");
+        unsafe { RawCell::from_mut_ptr(self.data.add(index)) }
+    }
+}
+
+impl Extend<i64> for RawFastVec {
+    fn extend<T: IntoIterator<Item = i64>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<i64> for RawFastVec {
+    fn from_iter<T: IntoIterator<Item = i64>>(iter: T) -> Self {
+        let mut vec = Self::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<'a> IntoIterator for &'a RawFastVec {
+    type Item = i64;
+    type IntoIter = RawIter<'a>;
+
+    fn into_iter(self) -> RawIter<'a> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_pop_and_growth() {
+        let mut vec = RawFastVec::new();
+        for i in 0..100 {
+            vec.push(i * i - 1_000_000_000_000);
+        }
+        assert_eq!(vec.len(), 100);
+        assert!(vec.capacity() >= 100);
+        assert_eq!(vec.get(10), Some(100 - 1_000_000_000_000));
+        assert_eq!(vec.get(100), None);
+        assert_eq!(vec.pop(), Some(99 * 99 - 1_000_000_000_000));
+        vec.truncate(3);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![-1_000_000_000_000, 1 - 1_000_000_000_000, 4 - 1_000_000_000_000]);
+    }
+
+    #[test]
+    fn views_and_indexing() {
+        let mut vec: RawFastVec = [5, i64::MIN, i64::MAX, 7].into_iter().collect();
+        vec[0].set(vec[3].get() * 2);
+        assert_eq!(vec[0].get(), 14);
+        let mut slice = vec.as_mut_slice();
+        slice.swap(1, 2);
+        slice.slice_mut(2..4).fill(-1);
+        assert_eq!(vec.as_slice().slice(1..3).to_vec(), vec![i64::MAX, -1]);
+        assert_eq!(vec.iter().rev().collect::<Vec<_>>(), vec![-1, -1, i64::MAX, 14]);
+        assert_eq!(format!("{:?}", vec.clone()), "[14, 9223372036854775807, -1, -1]");
+    }
+
+    #[test]
+    fn freed_memory_is_zeroed() {
+        let mut vec = RawFastVec::with_capacity(4);
+        vec.extend([i64::MAX, i64::MIN, 3, 4]);
+        let old_data = vec.data as usize;
+        vec.push(1);
+        assert_ne!(vec.data as usize, old_data);
+        for i in 0..4 {
+            assert_eq!(unsafe { _read_raw_i64(old_data + i) }, 0);
+        }
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![i64::MAX, i64::MIN, 3, 4, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn index_out_of_bounds() {
+        let vec: RawFastVec = [1, 2].into_iter().collect();
+        vec[2].get();
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds")]
+    fn swap_out_of_bounds() {
+        let mut vec: RawFastVec = [1, 2].into_iter().collect();
+        vec.as_mut_slice().swap(0, 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! Graph searches over nodes `0..n`, mainly for grids in the input.
+//!
+//! Nodes are plain indices and edges come from a `neighbors` closure, so the searches work on any graph.
+//! For an [InputGrid], the node of a cell is its input index, see [grid_neighbors4].
+//!
+//! Distances are kept in a [Distances] store, so no byte memory is needed:
+//! - [RawDistances] - one raw cell per node,
+//! - [InputDistances] - directly in the input through [set_input], overwriting it.
+
+use crate::collections::RawHeap;
+use crate::grid::InputGrid;
+use crate::instructions::{add_unchecked, eq};
+use crate::raw_vec::RawFastVec;
+use crate::{read_input, set_input};
+
+/// The distance of nodes which were not reached (yet).
+pub const UNREACHED: i64 = -1;
+
+/// Storage for the distance of every node.
+pub trait Distances {
+    fn get(&self, node: usize) -> i64;
+    fn set(&mut self, node: usize, distance: i64);
+
+    fn is_reached(&self, node: usize) -> bool {
+        !eq(self.get(node), UNREACHED)
+    }
+}
+
+/// Distances in raw cells, all nodes start as [UNREACHED].
+#[derive(Clone, Debug)]
+pub struct RawDistances(RawFastVec);
+
+impl RawDistances {
+    pub fn new(len: usize) -> Self {
+        RawDistances(std::iter::repeat_n(UNREACHED, len).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Distances for RawDistances {
+    fn get(&self, node: usize) -> i64 {
+        unsafe { self.0.get_unchecked(node) }
+    }
+
+    fn set(&mut self, node: usize, distance: i64) {
+        self.0.set(node, distance);
+    }
+}
+
+/// Distances stored in the input itself, the node is the input index.
+///
+/// Cells have to be set to [UNREACHED] before the search (e.g. with [InputGrid::rewrite]) to be visited.
+/// All other values count as already reached, so walls can simply be left as their characters.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InputDistances;
+
+impl Distances for InputDistances {
+    fn get(&self, node: usize) -> i64 {
+        read_input(node as u32)
+    }
+
+    fn set(&mut self, node: usize, distance: i64) {
+        set_input(node as u32, distance);
+    }
+}
+
+/// Iterates over the input indices of the in-bounds orthogonal neighbours of the cell at the input index.
+pub fn grid_neighbors4(grid: &InputGrid, node: usize) -> impl Iterator<Item = usize> + use<> {
+    let (x, y) = grid.position(node as u32);
+    let grid = *grid;
+    grid.neighbors4(x, y).map(move |(x, y)| grid.index(x, y) as usize)
+}
+
+/// Breadth-first search from a single node, see [multi_source_bfs].
+pub fn bfs<I: IntoIterator<Item = usize>>(start: usize, neighbors: impl FnMut(usize) -> I, distances: &mut impl Distances) -> usize {
+    multi_source_bfs(&[start], neighbors, distances)
+}
+
+/// Breadth-first search, sets the distance of every reachable node to the number of steps from the nearest start.
+///
+/// Only [UNREACHED] nodes are visited, so nodes with any other distance act as walls.
+/// Returns the number of nodes the search reached.
+pub fn multi_source_bfs<I: IntoIterator<Item = usize>>(starts: &[usize], mut neighbors: impl FnMut(usize) -> I, distances: &mut impl Distances) -> usize {
+    let mut queue = RawFastVec::new();
+    for &start in starts {
+        if !distances.is_reached(start) {
+            distances.set(start, 0);
+            queue.push(start as i64);
+        }
+    }
+    let mut head = 0;
+    while head < queue.len() {
+        let node = unsafe { queue.get_unchecked(head) } as usize;
+        head += 1;
+        let distance = add_unchecked(distances.get(node), 1);
+        for next in neighbors(node) {
+            if !distances.is_reached(next) {
+                distances.set(next, distance);
+                queue.push(next as i64);
+            }
+        }
+    }
+    queue.len()
+}
+
+/// Shortest paths with non-negative edge weights, `neighbors` returns `(node, weight)` pairs.
+///
+/// Sets the distance of every reachable node, like [multi_source_bfs] only [UNREACHED] nodes are visited.
+/// Returns the number of nodes the search reached.
+///
+/// # Safety
+/// This function leads to a program crash if a distance overflows i64.
+pub fn dijkstra<I: IntoIterator<Item = (usize, i64)>>(starts: &[usize], mut neighbors: impl FnMut(usize) -> I, distances: &mut impl Distances) -> usize {
+    // Tentative distances live in the heap, a node is final (and stored) once it is popped
+    let mut heap = RawHeap::new_min();
+    for &start in starts {
+        heap.decrease_key(0, start as i64);
+    }
+    let mut reached = 0;
+    while let Some((distance, node)) = heap.pop() {
+        let node = node as usize;
+        if distances.is_reached(node) {
+            continue;
+        }
+        distances.set(node, distance);
+        reached += 1;
+        for (next, weight) in neighbors(node) {
+            if !distances.is_reached(next) {
+                heap.decrease_key(add_unchecked(distance, weight), next as i64);
+            }
+        }
+    }
+    reached
+}
+
+/// Statistics of a region found by [flood_fill].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Number of cells.
+    pub area: usize,
+    /// Number of cell sides which border another character or the edge of the grid.
+    pub perimeter: usize,
+}
+
+/// Labels the region of orthogonally connected cells with the same character as the start cell.
+///
+/// Every cell of the region gets `label` in `labels`, cells which already have a label (i.e. are not
+/// [UNREACHED]) are skipped. The labels have to be stored separately from the grid, e.g. in [RawDistances].
+pub fn flood_fill(grid: &InputGrid, x: u32, y: u32, labels: &mut impl Distances, label: i64) -> Region {
+    let value = grid.get(x, y).to_i64();
+    let start = grid.index(x, y) as usize;
+    let mut region = Region { area: 0, perimeter: 0 };
+    if labels.is_reached(start) {
+        return region;
+    }
+    let mut stack = RawFastVec::new();
+    labels.set(start, label);
+    stack.push(start as i64);
+    while let Some(node) = stack.pop() {
+        region.area += 1;
+        let mut same = 0;
+        for next in grid_neighbors4(grid, node as usize) {
+            if !eq(read_input(next as u32), value) {
+                continue;
+            }
+            same += 1;
+            if !labels.is_reached(next) {
+                labels.set(next, label);
+                stack.push(next as i64);
+            }
+        }
+        region.perimeter += 4 - same;
+    }
+    region
+}
+
+/// Labels all regions of the grid with [flood_fill], labels are `0, 1, ...` in order of the returned regions.
+pub fn label_regions(grid: &InputGrid) -> (RawDistances, Vec<Region>) {
+    let mut labels = RawDistances::new(grid.index(0, grid.height()) as usize);
+    let mut regions = Vec::new();
+    for (x, y) in grid.positions() {
+        if !labels.is_reached(grid.index(x, y) as usize) {
+            let region = flood_fill(grid, x, y, &mut labels, regions.len() as i64);
+            regions.push(region);
+        }
+    }
+    (labels, regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native::set_input_str;
+
+    const MAZE: &str = "S..#....\n.#.#.##.\n.#...#..\n.####.#.\n......#E\n";
+
+    fn open_neighbors(grid: &InputGrid, node: usize) -> impl Iterator<Item = usize> + use<> {
+        grid_neighbors4(grid, node).filter(|&next| read_input(next as u32) != '#' as i64)
+    }
+
+    #[test]
+    fn bfs_in_raw_cells_and_input() {
+        set_input_str(MAZE);
+        let grid = InputGrid::new();
+        let start = grid.index(0, 0) as usize;
+        let end = grid.index(7, 4) as usize;
+
+        let mut distances = RawDistances::new(grid.index(0, grid.height()) as usize);
+        let reached = bfs(start, |node| open_neighbors(&grid, node), &mut distances);
+        assert_eq!(distances.get(end), 15);
+        assert_eq!(reached, 27);
+
+        // The same search with distances in the input, walls keep their character and are never visited
+        grid.rewrite(&[('.', UNREACHED), ('S', UNREACHED), ('E', UNREACHED)]);
+        let reached = bfs(start, |node| grid_neighbors4(&grid, node), &mut InputDistances);
+        assert_eq!(reached, 27);
+        assert_eq!(grid.get(7, 4).to_i64(), 15);
+        assert_eq!(grid.get(3, 0).to_i64(), '#' as i64);
+    }
+
+    #[test]
+    fn multi_source_and_dijkstra() {
+        set_input_str(MAZE);
+        let grid = InputGrid::new();
+        let starts = [grid.index(0, 0) as usize, grid.index(7, 4) as usize];
+        let mut distances = RawDistances::new(grid.index(0, grid.height()) as usize);
+        multi_source_bfs(&starts, |node| open_neighbors(&grid, node), &mut distances);
+        assert_eq!(distances.get(grid.index(4, 0) as usize), 7);
+        assert_eq!(distances.get(grid.index(3, 0) as usize), UNREACHED);
+
+        // Moving down costs 10, so the path avoids it as much as possible
+        let mut distances = RawDistances::new(grid.index(0, grid.height()) as usize);
+        let reached = dijkstra(&starts[..1], |node| {
+            let (_, y) = grid.position(node as u32);
+            open_neighbors(&grid, node).map(move |next| (next, if grid.position(next as u32).1 > y { 10 } else { 1 }))
+        }, &mut distances);
+        assert_eq!(reached, 27);
+        assert_eq!(distances.get(starts[1]), 69);
+    }
+
+    #[test]
+    fn regions() {
+        set_input_str("AAAA\nBBCD\nBBCC\nEEEC\n");
+        let grid = InputGrid::new();
+        let (labels, regions) = label_regions(&grid);
+        let stats: Vec<(usize, usize)> = regions.iter().map(|r| (r.area, r.perimeter)).collect();
+        assert_eq!(stats, vec![(4, 10), (4, 8), (4, 10), (1, 4), (3, 8)]);
+        assert_eq!(labels.get(grid.index(2, 2) as usize), 2);
+    }
+}
@@ -0,0 +1,298 @@
+//! Sorting and searching in raw i64 memory.
+//!
+//! All comparisons go through [cmp] (sgn + subabs + max) instead of generic i64 comparisons,
+//! which are very expensive after translation to ksplang.
+
+use crate::instructions::{cmp, div_unchecked, lensum, mul_unchecked, negate_unchecked, rem, sgn};
+use crate::raw_array::{RawSlice, RawSliceMut};
+use crate::raw_vec::RawFastVec;
+
+/// Runs shorter than this are sorted by insertion sort in [sort].
+const INSERTION_SORT_THRESHOLD: usize = 16;
+
+/// Sorts the values in ascending order with a least significant digit radix sort (base 10).
+///
+/// The number of passes is the number of decimal digits of the largest absolute value
+/// (found with lensum), digits are extracted with div and rem. This never compares two values,
+/// so it is the fastest option for many small numbers. Needs a scratch buffer of the same size.
+pub fn radix_sort(mut data: RawSliceMut) {
+    let n = data.len();
+    if n < 2 {
+        return;
+    }
+
+    let mut digits = 0;
+    for i in 0..n {
+        digits = digits.max(lensum(unsafe { data.get_unchecked(i) }, 0));
+    }
+
+    let mut scratch = zeroed_scratch(n);
+    let mut scratch = scratch.as_mut_slice();
+    let mut power: i64 = 1;
+    for digit in 0..digits {
+        // Digits of negative values are negative, we sort by the absolute value here
+        let mut counts = [0usize; 11];
+        for i in 0..n {
+            let d = digit_of(unsafe { data.get_unchecked(i) }, power);
+            counts[d + 1] += 1;
+        }
+        for d in 0..10 {
+            counts[d + 1] += counts[d];
+        }
+        for i in 0..n {
+            let value = unsafe { data.get_unchecked(i) };
+            let d = digit_of(value, power);
+            unsafe { scratch.set_unchecked(counts[d], value) };
+            counts[d] += 1;
+        }
+        copy(&scratch, &mut data);
+        if digit + 1 < digits {
+            power = mul_unchecked(power, 10);
+        }
+    }
+
+    // Values are now sorted by absolute value, so the negative ones have to be reversed and moved to the front.
+    let mut negative_count = 0;
+    for i in 0..n {
+        if sgn(unsafe { data.get_unchecked(i) }) == -1 {
+            negative_count += 1;
+        }
+    }
+    if negative_count == 0 {
+        return;
+    }
+    let mut negative_pos = negative_count;
+    let mut positive_pos = negative_count;
+    for i in 0..n {
+        let value = unsafe { data.get_unchecked(i) };
+        if sgn(value) == -1 {
+            negative_pos -= 1;
+            unsafe { scratch.set_unchecked(negative_pos, value) };
+        } else {
+            unsafe { scratch.set_unchecked(positive_pos, value) };
+            positive_pos += 1;
+        }
+    }
+    copy(&scratch, &mut data);
+}
+
+/// Sorts the values in ascending order, this sort is stable.
+///
+/// Runs of [INSERTION_SORT_THRESHOLD] values are sorted by insertion sort,
+/// then merged bottom-up using a scratch buffer of the same size.
+pub fn sort(data: RawSliceMut) {
+    sort_by_key(data, |x| x);
+}
+
+/// Sorts the values in ascending order of `key(value)`, this sort is stable.
+///
+/// The key is recomputed for every comparison, so it should be cheap.
+pub fn sort_by_key(mut data: RawSliceMut, key: impl Fn(i64) -> i64) {
+    let n = data.len();
+    let mut start = 0;
+    while start < n {
+        let end = (start + INSERTION_SORT_THRESHOLD).min(n);
+        insertion_sort_by_key(data.slice_mut(start..end), &key);
+        start = end;
+    }
+    if n <= INSERTION_SORT_THRESHOLD {
+        return;
+    }
+
+    let mut scratch = zeroed_scratch(n);
+    let mut scratch = scratch.as_mut_slice();
+    let mut width = INSERTION_SORT_THRESHOLD;
+    while width < n {
+        let mut lo = 0;
+        while lo < n {
+            let mid = (lo + width).min(n);
+            let hi = (lo + 2 * width).min(n);
+            merge(&data, &mut scratch, lo, mid, hi, &key);
+            lo = hi;
+        }
+        copy(&scratch, &mut data);
+        width *= 2;
+    }
+}
+
+/// Sorts the values in ascending order with insertion sort, this sort is stable.
+///
+/// Good for short or almost sorted arrays.
+pub fn insertion_sort(data: RawSliceMut) {
+    insertion_sort_by_key(data, &|x| x);
+}
+
+fn insertion_sort_by_key(mut data: RawSliceMut, key: &impl Fn(i64) -> i64) {
+    for i in 1..data.len() {
+        let value = unsafe { data.get_unchecked(i) };
+        let value_key = key(value);
+        let mut j = i;
+        while j > 0 && cmp(key(unsafe { data.get_unchecked(j - 1) }), value_key) == 1 {
+            unsafe { data.set_unchecked(j, data.get_unchecked(j - 1)) };
+            j -= 1;
+        }
+        unsafe { data.set_unchecked(j, value) };
+    }
+}
+
+/// Merges sorted `data[lo..mid]` and `data[mid..hi]` into `target[lo..hi]`.
+fn merge(data: &RawSliceMut, target: &mut RawSliceMut, lo: usize, mid: usize, hi: usize, key: &impl Fn(i64) -> i64) {
+    let mut left = lo;
+    let mut right = mid;
+    for i in lo..hi {
+        // Taking from the left on ties keeps the sort stable
+        let take_left = right == hi || (left < mid && cmp(key(unsafe { data.get_unchecked(left) }), key(unsafe { data.get_unchecked(right) })) != 1);
+        if take_left {
+            unsafe { target.set_unchecked(i, data.get_unchecked(left)) };
+            left += 1;
+        } else {
+            unsafe { target.set_unchecked(i, data.get_unchecked(right)) };
+            right += 1;
+        }
+    }
+}
+
+/// Searches a sorted slice for the value.
+///
+/// Returns `Ok(index)` of a matching value, or `Err(index)` where the value could be inserted
+/// to keep the slice sorted, just like [slice::binary_search].
+pub fn binary_search(data: RawSlice, value: i64) -> Result<usize, usize> {
+    let index = partition_point(data, |x| cmp(x, value) == -1);
+    match data.get(index) {
+        Some(found) if cmp(found, value) == 0 => Ok(index),
+        _ => Err(index),
+    }
+}
+
+/// Returns the index of the first value for which `pred` is false.
+///
+/// The slice has to be partitioned: all values where `pred` is true come first.
+pub fn partition_point(data: RawSlice, pred: impl Fn(i64) -> bool) -> usize {
+    let mut lo = 0;
+    let mut hi = data.len();
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(unsafe { data.get_unchecked(mid) }) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Removes consecutive repeated values by moving the unique ones to the front.
+///
+/// Returns the number of unique values, the rest of the slice is left in an unspecified state.
+/// Use [RawFastVec::truncate] to drop them from a vector.
+pub fn dedup(mut data: RawSliceMut) -> usize {
+    if data.is_empty() {
+        return 0;
+    }
+    let mut write = 1;
+    let mut last = unsafe { data.get_unchecked(0) };
+    for read in 1..data.len() {
+        let value = unsafe { data.get_unchecked(read) };
+        if cmp(value, last) != 0 {
+            unsafe { data.set_unchecked(write, value) };
+            write += 1;
+            last = value;
+        }
+    }
+    write
+}
+
+/// Returns the absolute value of the decimal digit of `value` at position `power` (a power of ten).
+fn digit_of(value: i64, power: i64) -> usize {
+    let digit = rem(div_unchecked(value, power), 10);
+    if sgn(digit) == -1 { negate_unchecked(digit) as usize } else { digit as usize }
+}
+
+fn zeroed_scratch(len: usize) -> RawFastVec {
+    let mut scratch = RawFastVec::with_capacity(len);
+    scratch.extend(std::iter::repeat_n(0, len));
+    scratch
+}
+
+fn copy(from: &RawSliceMut, to: &mut RawSliceMut) {
+    for i in 0..from.len() {
+        unsafe { to.set_unchecked(i, from.get_unchecked(i)) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw_array::RawFastArray;
+
+    const VALUES: [i64; 24] = [
+        5, -3, 0, i64::MAX, 17, i64::MIN, 5, -3, 1_000_000_007, 42, -42, 9, 10, 11, 0, 99,
+        -1, 1, 123_456_789_012, -987_654_321, 3, 3, i64::MIN + 1, 64,
+    ];
+
+    fn array_of(values: &[i64]) -> RawFastArray {
+        let mut array = RawFastArray::new_leaked(values.len());
+        for (i, &value) in values.iter().enumerate() {
+            array.set(i, value);
+        }
+        array
+    }
+
+    fn test_values(count: usize) -> Vec<i64> {
+        // A longer input so that the merge passes run too
+        let mut values: Vec<i64> = (0..count as i64).map(|i| (i * 7919 % 1009 - 500) * VALUES[i as usize % VALUES.len()].signum()).collect();
+        values.extend_from_slice(&VALUES);
+        values
+    }
+
+    #[test]
+    fn sorts_match_std() {
+        for count in [0, 1, 2, 15, 16, 17, 100, 300] {
+            let values = test_values(count);
+            let mut expected = values.clone();
+            expected.sort();
+
+            let mut array = array_of(&values);
+            radix_sort(array.as_mut_slice());
+            assert_eq!(array.as_slice().to_vec(), expected, "radix sort of {count}");
+
+            let mut array = array_of(&values);
+            sort(array.as_mut_slice());
+            assert_eq!(array.as_slice().to_vec(), expected, "sort of {count}");
+
+            let mut array = array_of(&values);
+            insertion_sort(array.as_mut_slice());
+            assert_eq!(array.as_slice().to_vec(), expected, "insertion sort of {count}");
+        }
+    }
+
+    #[test]
+    fn sort_by_key_is_stable() {
+        let values: Vec<i64> = (0..100).map(|i| (i % 7) * 1000 + i).collect();
+        let mut array = array_of(&values);
+        sort_by_key(array.as_mut_slice(), |x| x / 1000);
+        let mut expected = values.clone();
+        expected.sort_by_key(|x| x / 1000);
+        assert_eq!(array.as_slice().to_vec(), expected);
+    }
+
+    #[test]
+    fn searching() {
+        let array = array_of(&[i64::MIN, -5, 1, 1, 1, 8, i64::MAX]);
+        let slice = array.as_slice();
+        assert_eq!(binary_search(slice, -5), Ok(1));
+        assert!(matches!(binary_search(slice, 1), Ok(2..=4)));
+        assert_eq!(binary_search(slice, 0), Err(2));
+        assert_eq!(binary_search(slice, i64::MAX), Ok(6));
+        assert_eq!(binary_search(slice.slice(0..6), i64::MAX), Err(6));
+        assert_eq!(partition_point(slice, |x| cmp(x, 1) != 1), 5);
+    }
+
+    #[test]
+    fn dedup_values() {
+        let mut vec: RawFastVec = [1, 1, 2, 3, 3, 3, i64::MIN, i64::MAX, i64::MAX, -1, 1, 1].into_iter().collect();
+        let len = dedup(vec.as_mut_slice());
+        vec.truncate(len);
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![1, 2, 3, i64::MIN, i64::MAX, -1, 1]);
+    }
+}
@@ -0,0 +1,108 @@
+//! Debug traces from the WASM guest.
+//!
+//! [ksplang_trace!](crate::ksplang_trace) sends a tag and a few values to the host through the
+//! `env.trace_tag` and `env.trace_value` imports. wasm2ksplang keeps them as tiny functions which just
+//! pop their argument, so the host discards the values: nothing is recorded or printed, they can only be seen
+//! on top of the stack right before the pop when stepping through the program in annotools.
+//! Natively, traces are recorded (see [take_traces]) and printed to stderr only if the
+//! [TRACE_ENV_VAR](crate::native::TRACE_ENV_VAR) environment variable is set.
+//!
+//! Building with the `no-trace` feature of `common` (e.g. `cargo build --features common/no-trace`)
+//! compiles all traces to nothing.
+
+/// Whether traces are emitted, false with the `no-trace` feature.
+pub const ENABLED: bool = !cfg!(feature = "no-trace");
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+unsafe extern "C" {
+    /// Starts a new trace record with the given tag.
+    #[link_name = "trace_tag"]
+    fn _trace_tag(tag: i64);
+    /// Adds a value to the current trace record.
+    #[link_name = "trace_value"]
+    fn _trace_value(value: i64);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::native::env::{_trace_tag, _trace_value};
+
+/// Packs up to the first 8 bytes of a tag into an i64, so it is readable in a stack dump.
+///
+/// The first byte ends up in the lowest 8 bits.
+pub const fn tag(name: &str) -> i64 {
+    let bytes = name.as_bytes();
+    let mut result = 0i64;
+    let mut i = 0;
+    while i < bytes.len() && i < 8 {
+        result |= (bytes[i] as i64) << (8 * i);
+        i += 1;
+    }
+    result
+}
+
+/// Unpacks a tag created by [tag].
+pub fn tag_name(tag: i64) -> String {
+    tag.to_le_bytes().iter().take_while(|&&b| b != 0).map(|&b| b as char).collect()
+}
+
+#[doc(hidden)]
+pub fn trace_tag(tag: i64) {
+    unsafe { _trace_tag(tag) }
+}
+
+#[doc(hidden)]
+pub fn trace_value(value: i64) {
+    unsafe { _trace_value(value) }
+}
+
+/// Returns all trace records emitted on this thread so far (natively) and clears them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn take_traces() -> Vec<(String, Vec<i64>)> {
+    crate::native::take_traces()
+}
+
+/// Emits a debug trace with a tag (a string literal, see [tag](crate::trace::tag)) and any number of values.
+///
+/// ```
+/// let x = 5;
+/// common::ksplang_trace!("x", x, x * 2);
+/// # assert_eq!(common::trace::take_traces(), vec![("x".to_string(), vec![5, 10])]);
+/// ```
+///
+/// Values are converted with `as i64`. With the `no-trace` feature, nothing is emitted
+/// and the values are not evaluated.
+#[macro_export]
+macro_rules! ksplang_trace {
+    ($tag:expr $(, $value:expr)* $(,)?) => {
+        if $crate::trace::ENABLED {
+            const TAG: i64 = $crate::trace::tag($tag);
+            $crate::trace::trace_tag(TAG);
+            $( $crate::trace::trace_value(($value) as i64); )*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        assert_eq!(tag("a"), 'a' as i64);
+        assert_eq!(tag_name(tag("day7")), "day7");
+        assert_eq!(tag_name(tag("longer than eight")), "longer t");
+    }
+
+    #[test]
+    fn traces_are_recorded() {
+        take_traces();
+        crate::ksplang_trace!("empty");
+        crate::ksplang_trace!("values", 1u8, -2i32, i64::MAX,);
+        assert_eq!(take_traces(), vec![
+            ("empty".to_string(), vec![]),
+            ("values".to_string(), vec![1, -2, i64::MAX]),
+        ]);
+        assert!(take_traces().is_empty());
+    }
+}
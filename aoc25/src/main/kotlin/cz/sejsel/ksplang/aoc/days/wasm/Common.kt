@@ -1,6 +1,7 @@
 package cz.sejsel.ksplang.aoc.days.wasm
 
 import com.dylibso.chicory.runtime.Store
+import cz.sejsel.buildSingleModuleOutputProgram
 import cz.sejsel.buildSingleModuleProgram
 import cz.sejsel.ksplang.dsl.core.KsplangProgram
 import cz.sejsel.ksplang.dsl.core.ProgramFunction0To1
@@ -14,6 +15,8 @@ import java.nio.file.Path
 /**
  * A ksplang program which calls the main function - it should have no params and it should return one i32/i64 value.
  * It should interact with the input through imported functions: `env.read_input(index)` and `env.input_size`.
+ *
+ * Days whose solution returns `common::output::Output` need [buildWasmOutputProgram] instead.
  */
 fun buildWasmSingleValueProgram(wasmPath: Path, functionName: String): KsplangProgram {
     val translator = KsplangWasmModuleTranslator()
@@ -31,6 +34,18 @@ fun buildWasmSingleValueProgram(wasmPath: Path, functionName: String): KsplangPr
     }
 }
 
+/**
+ * A ksplang program which calls the main function - it should have no params and return `common::output::Output`,
+ * a pointer to `[len] [values...]`. Only the values are left on the stack, so text answers are printed as text.
+ */
+fun buildWasmOutputProgram(wasmPath: Path, functionName: String): KsplangProgram {
+    val translator = KsplangWasmModuleTranslator()
+
+    val store = Store()
+    val module = instantiateModuleFromPath(translator, wasmPath, "module", store)
+    return buildSingleModuleOutputProgram(module, functionName)
+}
+
 fun rebuildAllWasm() {
     val process = ProcessBuilder("./build-all.sh")
         .directory(File("aoc25/rust"))
@@ -213,7 +213,7 @@ fun buildSingleModuleProgram(
                 val indices = initializeNoMemoryWasmRuntimeData(runtimeData)
 
                 // The stack now starts with
-                // 0 input_len [globals] [fun_table] [input]
+                // 0 input_len [globals] [fun_table] [lookup] [input]
 
                 NoMemoryWasmBuilder(
                     builder = this@program,
@@ -225,7 +225,7 @@ fun buildSingleModuleProgram(
                 val indices = initializeSingleMemoryWasmRuntimeData(runtimeData)
 
                 // The stack now starts with
-                // 0 input_len [globals] [fun_table] [mem_size mem_max_size [mem_pages]] [input]
+                // 0 input_len [globals] [fun_table] [lookup] [mem_size mem_max_size [mem_pages]] [input]
 
                 SingleModuleWasmBuilder(
                     builder = this@program,
@@ -310,11 +310,33 @@ fun buildSingleModuleProgram(
                 }
             }
 
+            module.getLookupFunction()?.let {
+                it.setBody {
+                    with(builder) { lookup() }
+                }
+            }
+
             builder.block()
         }
     }
 }
 
+/**
+ * Build a ksplang program which runs an exported function returning a pointer to `[len] [values...]` in memory,
+ * and leaves only the values on the stack, the first one at the bottom.
+ */
+fun buildSingleModuleOutputProgram(module: InstantiatedKsplangWasmModule, functionName: String): KsplangProgram {
+    return buildSingleModuleProgram(module) {
+        val mainFunction = getExportedFunction(functionName) as ProgramFunction0To1
+
+        body {
+            call(mainFunction)
+            // pointer to [len] [values]
+            keepOnlyMemoryPtr() // destroys runtime layout
+        }
+    }
+}
+
 interface WasmBuilder {
     fun getExportedFunction(name: String): ProgramFunctionBase?
 
@@ -334,6 +356,8 @@ interface WasmBuilder {
     fun ComplexBlock.yeetMemory(): ComplexFunction
     fun ComplexBlock.getMemorySize(): ComplexFunction
     fun ComplexBlock.growMemory(): ComplexFunction
+    /** Signature: ```id i -> lookup_tables[id][i]``` */
+    fun ComplexBlock.lookup(): ComplexFunction
     /**
      * Given a memory pointer to a slice (len as first element, then len elements), remove everything
      * else on the ksplang stack. **Destroys the runtime layout**, useful at the end of programs.
@@ -374,6 +398,17 @@ class NoMemoryWasmBuilder(
         yoink()
     }
 
+    override fun ComplexBlock.lookup(): ComplexFunction = complexFunction("lookup") {
+        // id i
+        swap2()
+        push(indices.lookupStartIndex)
+        add()
+        yoink()
+        // i table_start
+        add()
+        yoink()
+    }
+
     /**
      * Signature: ```i -> input[i]```
      */
@@ -472,6 +507,17 @@ class SingleModuleWasmBuilder(
         yoink()
     }
 
+    override fun ComplexBlock.lookup(): ComplexFunction = complexFunction("lookup") {
+        // id i
+        swap2()
+        push(indices.lookupStartIndex)
+        add()
+        yoink()
+        // i table_start
+        add()
+        yoink()
+    }
+
     private fun ComplexBlock.toInputIndex() {
         // input starts at memStartIndex + memSize * 65536
         // i
@@ -793,21 +839,41 @@ fun InstantiatedKsplangWasmModule.toRuntimeData(): RuntimeData {
             }
         }
     }
-    return RuntimeData(globals, funTable, memories)
+    return RuntimeData(globals, funTable, memories, getLookupTables())
 }
 
 data class RuntimeData(
     val globals: List<GlobalInstance>,
     val funTable: List<ProgramFunctionBase?>,
     val memory: List<Memory>,
+    val lookupTables: Map<Int, List<Long>>,
 )
 
+/**
+ * Lookup tables as they are placed on the stack: a table start for every id up to the highest one
+ * (-1 for unused ids), followed by the values of all tables. Table starts are absolute stack indices.
+ */
+private fun RuntimeData.lookupStackData(startIndex: Int): List<Long> {
+    if (lookupTables.isEmpty()) return emptyList()
+    val idCount = lookupTables.keys.max() + 1
+    val starts = MutableList(idCount) { -1L }
+    val values = mutableListOf<Long>()
+    for (id in 0..<idCount) {
+        val table = lookupTables[id] ?: continue
+        starts[id] = (startIndex + idCount + values.size).toLong()
+        values.addAll(table)
+    }
+    return starts + values
+}
+
 data class SingleMemoryRuntimeIndexes(
     val inputLenIndex: Int,
     val globalsStartIndex: Int,
     val globalsCount: Int,
     val funTableStartIndex: Int,
     val funTableCount: Int,
+    val lookupStartIndex: Int,
+    val lookupSize: Int,
     val memSizeIndex: Int,
     val memMaxSizeIndex: Int,
     val memDataStartIndex: Int,
@@ -819,11 +885,13 @@ data class NoMemoryRuntimeIndexes(
     val globalsCount: Int,
     val funTableStartIndex: Int,
     val funTableCount: Int,
+    val lookupStartIndex: Int,
+    val lookupSize: Int,
     val inputStartIndex: Int
 )
 
 
-// input -> 0 input_len [globals] [fun_table] [input]
+// input -> 0 input_len [globals] [fun_table] [lookup] [input]
 //          ^ leaving that available for L-swap or other optimizations
 // input is statically adressable
 private fun ComplexBlock.initializeNoMemoryWasmRuntimeData(runtimeData: RuntimeData): NoMemoryRuntimeIndexes {
@@ -842,32 +910,40 @@ private fun ComplexBlock.initializeNoMemoryWasmRuntimeData(runtimeData: RuntimeD
         if (it != null) pushAddressOf(it) else push(-1)
     }
     // [input] 0 inputlen [globals] [fun_table]
+    val lookupStartIndex = 2 + runtimeData.globals.size + runtimeData.funTable.size
+    val lookup = runtimeData.lookupStackData(lookupStartIndex)
+    lookup.forEach {
+        push(it)
+    }
+    // [input] 0 inputlen [globals] [fun_table] [lookup]
 
-    val staticSize = 2 + runtimeData.globals.size + runtimeData.funTable.size
+    val staticSize = 2 + runtimeData.globals.size + runtimeData.funTable.size + lookup.size
     dupKthZeroIndexed(staticSize - 2)
 
-    // [input] 0 inputlen [globals] [fun_table] inputlen
+    // [input] 0 inputlen [globals] [fun_table] [lookup] inputlen
     dup()
     push(staticSize)
     add()
-    // [input] 0 inputlen [globals] [fun_table] inputlen inputlen+static_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] inputlen inputlen+static_size
     swap2()
     negate()
     swap2()
-    // [input] 0 inputlen [globals] [fun_table] -inputlen inputlen+static_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] -inputlen inputlen+static_size
     lroll()
-    // 0 inputlen [globals] [fun_table] [input]
+    // 0 inputlen [globals] [fun_table] [lookup] [input]
     return NoMemoryRuntimeIndexes(
         inputLenIndex = 1,
         globalsStartIndex = 2,
         globalsCount = runtimeData.globals.size,
         funTableStartIndex = 2 + runtimeData.globals.size,
         funTableCount = runtimeData.funTable.size,
-        inputStartIndex = 2 + runtimeData.globals.size + runtimeData.funTable.size,
+        lookupStartIndex = lookupStartIndex,
+        lookupSize = lookup.size,
+        inputStartIndex = staticSize,
     )
 }
 
-// input -> 0 input_len [globals] [fun_table] [mem_size mem_max_size [mem_pages]] [input]
+// input -> 0 input_len [globals] [fun_table] [lookup] [mem_size mem_max_size [mem_pages]] [input]
 //          ^ leaving that available for L-swap or other optimizations
 // everything up to mem_pages is static size, so we can have access to memory without indirect addressing
 // input moves with memory growth, it starts at static_size + mem_size * 65536
@@ -893,10 +969,16 @@ private fun ComplexBlock.initializeSingleMemoryWasmRuntimeData(runtimeData: Runt
         }
     }
     // [input] 0 inputlen [globals] [fun_table]
+    val lookupStartIndex = 2 + runtimeData.globals.size + runtimeData.funTable.size
+    val lookup = runtimeData.lookupStackData(lookupStartIndex)
+    lookup.forEach {
+        push(it)
+    }
+    // [input] 0 inputlen [globals] [fun_table] [lookup]
     push(memory.pages())
-    // [input] 0 inputlen [globals] [fun_table] mem_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] mem_size
     push(memory.maximumPages())
-    // [input] 0 inputlen [globals] [fun_table] mem_size mem_max_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] mem_size mem_max_size
 
     val chunks = MemoryChunker.chunkMemory(memory, minZeroesToChunk = 200)
     chunks.forEach {
@@ -909,20 +991,21 @@ private fun ComplexBlock.initializeSingleMemoryWasmRuntimeData(runtimeData: Runt
         }
     }
 
-    val staticSize = 2 + runtimeData.globals.size + runtimeData.funTable.size + 2
+    val memSizeIndex = lookupStartIndex + lookup.size
+    val staticSize = memSizeIndex + 2
     val memorySize = memory.pages() * 65536L
     dupKthZeroIndexed(staticSize + memorySize - 2)
-    // [input] 0 inputlen [globals] [fun_table] mem_size mem_max_size [mem] inputlen
+    // [input] 0 inputlen [globals] [fun_table] [lookup] mem_size mem_max_size [mem] inputlen
     dup()
     push(staticSize + memorySize)
     add()
-    // [input] 0 inputlen [globals] [fun_table] mem_size mem_max_size [mem] inputlen inputlen+static_size+mem_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] mem_size mem_max_size [mem] inputlen inputlen+static_size+mem_size
     swap2()
     negate()
     swap2()
-    // [input] 0 inputlen [globals] [fun_table] mem_size mem_max_size [mem] -inputlen inputlen+static_size+mem_size
+    // [input] 0 inputlen [globals] [fun_table] [lookup] mem_size mem_max_size [mem] -inputlen inputlen+static_size+mem_size
     lroll()
-    // 0 inputlen [globals] [fun_table] mem_size mem_max_size [mem] [input]
+    // 0 inputlen [globals] [fun_table] [lookup] mem_size mem_max_size [mem] [input]
 
     return SingleMemoryRuntimeIndexes(
         inputLenIndex = 1,
@@ -930,9 +1013,11 @@ private fun ComplexBlock.initializeSingleMemoryWasmRuntimeData(runtimeData: Runt
         globalsCount = runtimeData.globals.size,
         funTableStartIndex = 2 + runtimeData.globals.size,
         funTableCount = runtimeData.funTable.size,
-        memSizeIndex = 2 + runtimeData.globals.size + runtimeData.funTable.size,
-        memMaxSizeIndex = 3 + runtimeData.globals.size + runtimeData.funTable.size,
-        memDataStartIndex = 4 + runtimeData.globals.size + runtimeData.funTable.size,
+        lookupStartIndex = lookupStartIndex,
+        lookupSize = lookup.size,
+        memSizeIndex = memSizeIndex,
+        memMaxSizeIndex = memSizeIndex + 1,
+        memDataStartIndex = memSizeIndex + 2,
     )
 }
 
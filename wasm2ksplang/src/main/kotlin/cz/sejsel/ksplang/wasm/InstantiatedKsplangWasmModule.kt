@@ -12,6 +12,7 @@ import cz.sejsel.ksplang.dsl.core.ProgramFunction0To1
 import cz.sejsel.ksplang.dsl.core.ProgramFunction1To0
 import cz.sejsel.ksplang.dsl.core.ProgramFunction1To1
 import cz.sejsel.ksplang.dsl.core.ProgramFunction2To0
+import cz.sejsel.ksplang.dsl.core.ProgramFunction2To1
 import cz.sejsel.ksplang.dsl.core.ProgramFunctionBase
 import java.nio.file.Path
 
@@ -25,6 +26,10 @@ class InstantiatedKsplangWasmModule(val moduleName: String, val module: Translat
         store.addFunction(HostFunction("env", "save_raw_i64", FunctionType.of(listOf(ValType.I64, ValType.I32), listOf())) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("env", "read_raw_i64", FunctionType.of(listOf(ValType.I32), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("env", "set_input", FunctionType.of(listOf(ValType.I64, ValType.I32), listOf())) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("env", "lookup", FunctionType.of(listOf(ValType.I32, ValType.I32), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("env", "fail", FunctionType.of(listOf(ValType.I64), listOf())) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("env", "trace_tag", FunctionType.of(listOf(ValType.I64), listOf())) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("env", "trace_value", FunctionType.of(listOf(ValType.I64), listOf())) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "max", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "u_add", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "u_subabs", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
@@ -42,8 +47,18 @@ class InstantiatedKsplangWasmModule(val moduleName: String, val module: Translat
         store.addFunction(HostFunction("ksplang", "gcd", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "funkcia", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "spanek", FunctionType.of(listOf(), listOf())) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "bulkxor", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "qeq_count", FunctionType.of(listOf(ValType.I64, ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "qeq_single", FunctionType.of(listOf(ValType.I64, ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "qeq_root", FunctionType.of(listOf(ValType.I64, ValType.I64, ValType.I64, ValType.I32), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "negate", FunctionType.of(listOf(ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
         store.addFunction(HostFunction("ksplang", "div", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "is_zero", FunctionType.of(listOf(ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "is_nonzero", FunctionType.of(listOf(ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "eq", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "lt", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "le", FunctionType.of(listOf(ValType.I64, ValType.I64), listOf(ValType.I32))) { _, _ -> error("Dummy function") })
+        store.addFunction(HostFunction("ksplang", "select", FunctionType.of(listOf(ValType.I64, ValType.I64, ValType.I32), listOf(ValType.I64))) { _, _ -> error("Dummy function") })
     }
     val instance: Instance = store.instantiate(moduleName, module.chicoryModule)
 
@@ -87,6 +102,12 @@ class InstantiatedKsplangWasmModule(val moduleName: String, val module: Translat
 
     fun getSetInputFunction(): ProgramFunction2To0? = module.setInputFunction
 
+    /** Function for lookup, must have body set by the embedder. */
+    fun getLookupFunction(): ProgramFunction2To1? = module.lookupFunction
+
+    /** Constant lookup tables by id, must be placed on the stack by the embedder. */
+    fun getLookupTables(): Map<Int, List<Long>> = module.lookupTables
+
     fun install(builder: KsplangProgramBuilder) {
         with(builder) {
             with(module) { installFunctions() }
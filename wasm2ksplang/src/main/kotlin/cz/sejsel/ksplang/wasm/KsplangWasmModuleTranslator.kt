@@ -6,6 +6,7 @@ import com.dylibso.chicory.wasm.types.AnnotatedInstruction
 import com.dylibso.chicory.wasm.types.ExternalType
 import com.dylibso.chicory.wasm.types.FunctionImport
 import com.dylibso.chicory.wasm.types.OpCode
+import com.dylibso.chicory.wasm.types.UnknownCustomSection
 import com.dylibso.chicory.wasm.types.ValType
 import cz.sejsel.ksplang.dsl.core.CallInline
 import cz.sejsel.ksplang.dsl.core.ComplexBlock
@@ -94,14 +95,25 @@ import cz.sejsel.ksplang.dsl.core.ProgramFunction8To7
 import cz.sejsel.ksplang.dsl.core.ProgramFunction8To8
 import cz.sejsel.ksplang.dsl.core.ProgramFunctionBase
 import cz.sejsel.ksplang.dsl.core.buildComplexFunction
+import cz.sejsel.ksplang.dsl.core.ifZero
+import cz.sejsel.ksplang.dsl.core.otherwise
+import cz.sejsel.ksplang.std.abs
 import cz.sejsel.ksplang.std.add
+import cz.sejsel.ksplang.std.cmp
 import cz.sejsel.ksplang.std.cursedDiv
 import cz.sejsel.ksplang.std.div
 import cz.sejsel.ksplang.std.factorial
+import cz.sejsel.ksplang.std.leq
+import cz.sejsel.ksplang.std.lt
 import cz.sejsel.ksplang.std.mul
 import cz.sejsel.ksplang.std.negate
+import cz.sejsel.ksplang.std.push
+import cz.sejsel.ksplang.std.roll
 import cz.sejsel.ksplang.std.sgn
 import cz.sejsel.ksplang.std.subabs
+import cz.sejsel.ksplang.std.zeroNot
+import java.nio.ByteBuffer
+import java.nio.ByteOrder
 import java.nio.file.Path
 import cz.sejsel.ksplang.wasm.WasmFunctionScope.Companion.initialize as initializeScope
 
@@ -134,6 +146,10 @@ class TranslatedWasmModule(
     val readRawFunction: ProgramFunction1To1?,
     /** Forward declaration, needs to be implemented by embedder */
     val setInputFunction: ProgramFunction2To0?,
+    /** Forward declaration, needs to be implemented by embedder */
+    val lookupFunction: ProgramFunction2To1?,
+    /** Constant tables from the [LOOKUP_SECTION] custom section by table id, the embedder places them on the stack. */
+    val lookupTables: Map<Int, List<Long>>,
 ) {
     fun KsplangProgramBuilder.installFunctions() {
         programFunctions.forEach { installFunction(it) }
@@ -149,6 +165,7 @@ class TranslatedWasmModule(
         saveRawFunction?.let { installFunction(it) }
         readRawFunction?.let { installFunction(it) }
         setInputFunction?.let { installFunction(it) }
+        lookupFunction?.let { installFunction(it) }
     }
 
     fun getFunction(index: Int): ProgramFunctionBase? {
@@ -178,6 +195,7 @@ class ModuleTranslatorState {
     var readRawFunction: ProgramFunction1To1? = null
     var getFunctionAddressFunction: ProgramFunction1To1? = null
     var setInputFunction: ProgramFunction2To0? = null
+    var lookupFunction: ProgramFunction2To1? = null
 
     fun getFunctionAddressFunction(): ProgramFunction1To1 {
         // Forward declaration.
@@ -333,6 +351,17 @@ class KsplangWasmModuleTranslator() {
             state.setInputFunction = it.function as ProgramFunction2To0
         }
 
+        importedFunctions["env" to "lookup"]?.let {
+            state.lookupFunction = it.function as ProgramFunction2To1
+        }
+
+        // common::fail - crash on purpose (mod by zero pops its arguments), leaving [code, "FAIL"] on top of the stack
+        importedFunctions["env" to "fail"]?.let { it.function.setBody { push(FAIL_MARKER); push(0); push(0); modulo() } }
+
+        // Debug traces (common::trace) discard their values, they are only visible when stepping through the program
+        importedFunctions["env" to "trace_tag"]?.let { it.function.setBody { pop() } }
+        importedFunctions["env" to "trace_value"]?.let { it.function.setBody { pop() } }
+
         importedFunctions["ksplang" to "max"]?.let { it.function.setBody { max2() } }
         importedFunctions["ksplang" to "u_add"]?.let { it.function.setBody { add() } }
         importedFunctions["ksplang" to "u_subabs"]?.let { it.function.setBody { subabs() } }
@@ -350,8 +379,46 @@ class KsplangWasmModuleTranslator() {
         importedFunctions["ksplang" to "gcd"]?.let { it.function.setBody { gcd() } }
         importedFunctions["ksplang" to "funkcia"]?.let { it.function.setBody { funkcia() } }
         importedFunctions["ksplang" to "spanek"]?.let { it.function.setBody { spanek() } }
+        importedFunctions["ksplang" to "bulkxor"]?.let { it.function.setBody { push(1); bulkxor() } }
+        // qeq pushes 0, 1 or 2 roots (c b a -> roots), the wrappers know how many to expect.
+        // qeq_count: c != 0, so a 0 below the roots is a sentinel which cannot be a root
+        importedFunctions["ksplang" to "qeq_count"]?.let {
+            it.function.setBody {
+                // c b a -> 0 c b a
+                push(0); roll(4, 1)
+                qeq()
+                // 0 [r1] [r2], the digit sum of a root is never 0
+                CS()
+                ifZero(popChecked = true) {
+                    // 0 - no roots, the sentinel is the count
+                } otherwise {
+                    // 0 [r1] r
+                    pop()
+                    CS()
+                    ifZero(popChecked = true) {
+                        // 0
+                        inc()
+                    } otherwise {
+                        // 0 r1
+                        pop(); inc(); inc()
+                    }
+                }
+            }
+        }
+        // Exactly one root
+        importedFunctions["ksplang" to "qeq_single"]?.let { it.function.setBody { qeq() } }
+        // Exactly two roots: c b a index -> index c b a -> index r1 r2 -> r1 r2 index, then like select
+        importedFunctions["ksplang" to "qeq_root"]?.let { it.function.setBody { roll(4, 1); qeq(); roll(3, 2); push(2); lroll(); pop() } }
         importedFunctions["ksplang" to "negate"]?.let { it.function.setBody { negate() } }
         importedFunctions["ksplang" to "div"]?.let { it.function.setBody { div() } }
+        // Branch-free comparisons, results are 0 or 1
+        importedFunctions["ksplang" to "is_zero"]?.let { it.function.setBody { zeroNot() } }
+        importedFunctions["ksplang" to "is_nonzero"]?.let { it.function.setBody { sgn(); abs() } }
+        importedFunctions["ksplang" to "eq"]?.let { it.function.setBody { cmp(); zeroNot() } }
+        importedFunctions["ksplang" to "lt"]?.let { it.function.setBody { lt() } }
+        importedFunctions["ksplang" to "le"]?.let { it.function.setBody { leq() } }
+        // if_false if_true cond -> roll the two values by cond, then drop the top one
+        importedFunctions["ksplang" to "select"]?.let { it.function.setBody { push(2); lroll(); pop() } }
 
         return TranslatedWasmModule(
             programFunctions = functions.map { it.function },
@@ -369,7 +436,9 @@ class KsplangWasmModuleTranslator() {
             saveRawFunction = state.saveRawFunction,
             readRawFunction = state.readRawFunction,
             isMemoryUsed = isMemoryUsed,
-            setInputFunction = state.setInputFunction
+            setInputFunction = state.setInputFunction,
+            lookupFunction = state.lookupFunction,
+            lookupTables = readLookupTables(module),
         )
     }
 
@@ -1072,4 +1141,36 @@ class KsplangWasmModuleTranslator() {
             throw UnsupportedOperationException("Exception Handling extension is not supported")
         }
     }
-}
\ No newline at end of file
+}
+
+/** Name of the custom section with constant lookup tables (see common::lookup in the Rust crates). */
+const val LOOKUP_SECTION = "ksplang_lookup"
+
+/** Table ids are indices into a table of offsets on the stack, so they have to stay small. */
+const val MAX_LOOKUP_TABLE_ID = 1024
+
+/**
+ * Reads all tables from the [LOOKUP_SECTION] custom section.
+ *
+ * The section is a concatenation of records `[table_id] [len] [value_0] ... [value_len-1]`,
+ * all little-endian i64 values. Table ids must be unique.
+ */
+fun readLookupTables(module: WasmModule): Map<Int, List<Long>> {
+    val section = module.customSections()
+        .filterIsInstance<UnknownCustomSection>()
+        .firstOrNull { it.name() == LOOKUP_SECTION } ?: return emptyMap()
+    val buffer = ByteBuffer.wrap(section.bytes()).order(ByteOrder.LITTLE_ENDIAN)
+    val tables = mutableMapOf<Int, List<Long>>()
+    while (buffer.hasRemaining()) {
+        val id = buffer.getLong()
+        val len = buffer.getLong()
+        check(id in 0..<MAX_LOOKUP_TABLE_ID) { "Lookup table id $id is out of range 0..<$MAX_LOOKUP_TABLE_ID" }
+        check(id.toInt() !in tables) { "Duplicate lookup table id $id" }
+        check(len >= 0 && len * 8 <= buffer.remaining()) { "Lookup table $id is truncated" }
+        tables[id.toInt()] = List(len.toInt()) { buffer.getLong() }
+    }
+    return tables
+}
+
+/** Marker on top of the stack after `env.fail`, the bytes of "FAIL" (see common::failure in the Rust crates). */
+const val FAIL_MARKER = 0x4C494146L
@@ -0,0 +1,54 @@
+package cz.sejsel.ksplang.wasm.runtime.singlemodule
+
+import com.dylibso.chicory.runtime.Store
+import cz.sejsel.buildSingleModuleOutputProgram
+import cz.sejsel.ksplang.DefaultKsplangRunner
+import cz.sejsel.ksplang.builder.KsplangBuilder
+import cz.sejsel.ksplang.wasm.KsplangWasmModuleTranslator
+import cz.sejsel.ksplang.wasm.instantiateModuleFromWat
+import io.kotest.core.spec.style.FunSpec
+import io.kotest.matchers.shouldBe
+
+class OutputTests : FunSpec({
+    val runner = DefaultKsplangRunner(defaultOpLimit = 100_000_000)
+    val builder = KsplangBuilder()
+    val translator = KsplangWasmModuleTranslator()
+
+    context("output program") {
+        // What common::output::Output::text("6,7") does: [len] and the code of every character
+        // are written to raw cells with save_raw_i64, solve returns the pointer
+        val wat = $$"""
+                (module
+                    (import "env" "save_raw_i64" (func $save_raw_i64 (param i64 i32)))
+                    (memory 1)
+                    (func $solve (export "solve") (result i32)
+                        ;; the allocation of a real module, memory would be dropped as unused without it
+                        memory.size
+                        drop
+                        i64.const 3
+                        i32.const 16
+                        call $save_raw_i64
+                        i64.const 54
+                        i32.const 17
+                        call $save_raw_i64
+                        i64.const 44
+                        i32.const 18
+                        call $save_raw_i64
+                        i64.const 55
+                        i32.const 19
+                        call $save_raw_i64
+                        i32.const 16
+                    )
+                )""".trimIndent()
+
+        val store = Store()
+        val module = instantiateModuleFromWat(translator, wat, "test", store)
+        val program = buildSingleModuleOutputProgram(module, "solve")
+        val ksplang = builder.buildAnnotated(program).toRunnableProgram()
+
+        val result = runner.run(ksplang, listOf(40L, 2L))
+        test("only the text is left on the stack") {
+            result shouldBe "6,7".map { it.code.toLong() }
+        }
+    }
+})